@@ -0,0 +1,84 @@
+//! Compare scanning a large fixture sequentially and in parallel.
+//!
+//! Run with `cargo bench --features rayon`.
+
+extern crate crc32fast;
+extern crate dwprod;
+extern crate flate2;
+extern crate gimli;
+extern crate object;
+extern crate ruzstd;
+
+#[path = "../tests/support/mod.rs"]
+mod support;
+
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use support::{UnitSpec, Value};
+
+/// Roughly the number of compilation units in a Chromium-sized binary.
+const UNITS_PER_FILE: usize = 100_000;
+const FILES: usize = 4;
+const ITERATIONS: usize = 5;
+
+fn large_fixture(index: usize) -> PathBuf {
+    let mut dwarf = support::DwarfBuilder::new(object::Endianness::Little);
+    for i in 0..UNITS_PER_FILE {
+        dwarf.unit(
+            &UnitSpec::new(&format!("clang version 17.0.6 -O2 -g -ffile-{}", i % 97))
+                .attr(gimli::DW_AT_name, Value::Strp(format!("src/file{}.cc", i)))
+                .attr(gimli::DW_AT_comp_dir, Value::Strp("/build/out".into()))
+                .attr(gimli::DW_AT_language, Value::Data2(gimli::DW_LANG_C_plus_plus_14.0)),
+        );
+    }
+    let data = support::Elf::new().dwarf(&dwarf).write();
+    support::write_fixture(&format!("bench/large-{}.o", index), &data)
+}
+
+/// The best time out of several runs of the scan.
+fn time(paths: &[PathBuf], parallel: bool) -> (Duration, Vec<dwprod::ScannedFile>) {
+    let mut best = None;
+    let mut files = vec![];
+    for _ in 0..ITERATIONS {
+        let mut scan = dwprod::Scan::new().parallel(parallel);
+        for path in paths {
+            scan = scan.path(path);
+        }
+
+        let start = Instant::now();
+        files = scan.run();
+        let elapsed = start.elapsed();
+        best = Some(best.map_or(elapsed, |best: Duration| best.min(elapsed)));
+    }
+    (best.unwrap(), files)
+}
+
+fn summary(files: &[dwprod::ScannedFile]) -> Vec<(usize, usize)> {
+    files
+        .iter()
+        .map(|file| (file.units().count(), file.errors().count()))
+        .collect()
+}
+
+fn main() {
+    println!(
+        "Writing {} fixtures with {} compilation units each...",
+        FILES, UNITS_PER_FILE
+    );
+    let paths: Vec<_> = (0..FILES).map(large_fixture).collect();
+
+    let (sequential, sequential_files) = time(&paths, false);
+    let (parallel, parallel_files) = time(&paths, true);
+
+    assert_eq!(summary(&sequential_files), summary(&parallel_files));
+    for (a, b) in sequential_files.iter().zip(&parallel_files) {
+        assert!(a.units().eq(b.units()), "results should be in the same order");
+    }
+
+    println!("sequential: {:?}", sequential);
+    println!("parallel:   {:?}", parallel);
+    println!(
+        "speedup:    {:.2}x",
+        sequential.as_secs_f64() / parallel.as_secs_f64()
+    );
+}
@@ -0,0 +1,62 @@
+//! Static libraries: `ar` archives in the GNU and BSD variants, thin archives
+//! whose members are separate files, and Rust's `.rlib`s.
+
+use super::{file_range, Result};
+use object::read::archive::ArchiveFile;
+use std::ops::Range;
+use std::path::PathBuf;
+
+/// The names of the members of `.rlib`s that hold Rust's crate metadata,
+/// rather than code: `lib.rmeta` these days, and `rust.metadata.bin` before
+/// Rust 1.43.
+const RLIB_METADATA: &[&str] = &["lib.rmeta", "rust.metadata.bin"];
+
+/// An object file within an archive.
+#[derive(Debug)]
+pub struct Member {
+    /// The member's name, as given by `ar t`.
+    pub name: String,
+    /// Where the member's data is.
+    pub location: Location,
+}
+
+/// Where an archive member's data is.
+#[derive(Debug)]
+pub enum Location {
+    /// Within the archive, at this range.
+    Within(Range<usize>),
+    /// In a separate file, at this path relative to the thin archive's
+    /// directory.
+    Thin(PathBuf),
+}
+
+/// Whether `data` starts with the magic number of an `ar` archive, either a
+/// regular or a thin one.
+pub fn is_archive(data: &[u8]) -> bool {
+    data.starts_with(b"!<arch>\n") || data.starts_with(b"!<thin>\n")
+}
+
+/// Get the members of the archive `data`, in order.
+///
+/// Symbol tables and long name tables, which `ar` adds itself, are not
+/// members, and neither is the crate metadata in `.rlib`s.
+pub fn members(data: &[u8]) -> Result<Vec<Member>> {
+    let archive = ArchiveFile::parse(data)?;
+    let mut members = vec![];
+    for member in archive.members() {
+        let member = member?;
+        let name = String::from_utf8_lossy(member.name()).into_owned();
+        if RLIB_METADATA.contains(&&*name) {
+            continue;
+        }
+
+        let location = if member.is_thin() {
+            Location::Thin(PathBuf::from(&name))
+        } else {
+            let (offset, size) = member.file_range();
+            Location::Within(file_range("archive member", offset, size, data.len())?)
+        };
+        members.push(Member { name, location });
+    }
+    Ok(members)
+}
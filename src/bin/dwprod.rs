@@ -1,8 +1,22 @@
 extern crate clap;
 extern crate dwprod;
+#[macro_use]
+extern crate serde_json;
 
+use dwprod::{
+    Channel, CompilationUnit, Compiler, Diagnostic, Producer, ScannedFile, ToolchainRecord,
+    UnitKind, WasmProducers, WasmTool,
+};
+use serde_json::Value;
+use std::io::{self, Read};
+use std::path::Path;
 use std::process;
 
+/// The version of the `--format json` and `--format ndjson` output schema.
+/// This is bumped whenever a field is removed or changes meaning; new fields
+/// may be added without bumping it.
+const SCHEMA_VERSION: u32 = 2;
+
 fn main() {
     if let Err(e) = try_main() {
         eprintln!("Error: {}", e);
@@ -13,15 +27,320 @@ fn main() {
 fn try_main() -> dwprod::Result<()> {
     let matches = parse_args();
 
-    let opts = dwprod::Options::new(matches.value_of("file").unwrap());
+    let paths: Vec<&str> = matches.values_of("file").unwrap().collect();
+    // Standard input can only be read once, so a second `-` would be empty.
+    if paths.iter().filter(|path| **path == "-").count() > 1 {
+        return Err("standard input (`-`) can only be given once".into());
+    }
+    let mut scan = dwprod::Scan::new();
+    for path in &paths {
+        if *path == "-" {
+            let mut data = vec![];
+            io::stdin().read_to_end(&mut data)?;
+            scan = scan.data("<stdin>", data);
+        } else {
+            scan = scan.path(path);
+        }
+    }
+    for dir in matches.values_of("debug-dir").into_iter().flatten() {
+        scan = scan.debug_search_dir(dir);
+    }
+    scan = scan
+        .toolchain_fallback(matches.is_present("toolchain-fallback"))
+        .type_units(matches.is_present("type-units"))
+        .lenient(matches.is_present("lenient"));
+
+    // Errors in individual files or compilation units, such as a missing
+    // `.dwo` file, shouldn't stop us from reporting the rest. With
+    // `--lenient`, units that can't be read are only warned about.
+    let files = scan.run();
+
+    // Like `grep`, only prefix results with their file when there could be
+    // more than one file. Archive members and the architectures of universal
+    // binaries are always prefixed, even when there is only one.
+    let prefix = paths.len() > 1
+        || files.len() > 1
+        || paths.iter().any(|path| Path::new(path).is_dir())
+        || files.iter().any(|file| file.member().is_some() || file.arch().is_some());
+    match matches.value_of("format").unwrap() {
+        "json" => print_json(&files),
+        "ndjson" => print_ndjson(&files),
+        _ => print_text(&files, prefix),
+    }
+
+    if files.iter().any(|file| file.errors().next().is_some()) {
+        process::exit(1);
+    }
+    Ok(())
+}
+
+/// Print one producer per line.
+fn print_text(files: &[ScannedFile], prefix: bool) {
+    for file in files {
+        for result in file.results() {
+            match *result {
+                Ok(ref unit) => {
+                    let producer = match unit.producer() {
+                        Some(producer) => producer.to_string(),
+                        None => continue,
+                    };
+                    // Type units are labelled, so that they can be told apart
+                    // from, or filtered out of, the compilation units.
+                    let producer = match unit.kind() {
+                        UnitKind::Compile => producer,
+                        UnitKind::Type => format!("type unit: {}", producer),
+                    };
+                    if prefix {
+                        println!("{}: {}", name(file), producer);
+                    } else {
+                        println!("{}", producer);
+                    }
+                }
+                Err(ref e) => {
+                    if prefix {
+                        eprintln!("Error: {}: {}", name(file), e);
+                    } else {
+                        eprintln!("Error: {}", e);
+                    }
+                }
+            }
+        }
+
+        for diagnostic in file.diagnostics() {
+            if prefix {
+                eprintln!("Warning: {}: {}", name(file), diagnostic);
+            } else {
+                eprintln!("Warning: {}", diagnostic);
+            }
+        }
+
+        // A malformed `producers` section doesn't keep the module's DWARF from
+        // being read, so it is only warned about.
+        if let Some(e) = file.wasm_producers_error() {
+            if prefix {
+                eprintln!("Warning: {}: producers section: {}", name(file), e);
+            } else {
+                eprintln!("Warning: producers section: {}", e);
+            }
+        }
+
+        // WebAssembly modules also say what built them in their `producers`
+        // section, as lines like `processed-by: rustc 1.75.0`, and stripped
+        // binaries may have toolchain records, labelled by their section.
+        let records = file.toolchain_records().iter().map(|record| record.to_string());
+        let lines = file.wasm_producers().map(wasm_producer_lines).into_iter().flatten();
+        for line in lines.chain(records) {
+            if prefix {
+                println!("{}: {}", name(file), line);
+            } else {
+                println!("{}", line);
+            }
+        }
+    }
+}
+
+fn wasm_producer_lines(producers: &WasmProducers) -> Vec<String> {
+    let fields = [
+        ("language", producers.language()),
+        ("processed-by", producers.processed_by()),
+        ("sdk", producers.sdk()),
+    ];
+    let mut lines = vec![];
+    for &(field, tools) in &fields {
+        for tool in tools {
+            if tool.version.is_empty() {
+                lines.push(format!("{}: {}", field, tool.name));
+            } else {
+                lines.push(format!("{}: {} {}", field, tool.name, tool.version));
+            }
+        }
+    }
+    lines
+}
+
+/// Print a single JSON document describing every unit of every file.
+fn print_json(files: &[ScannedFile]) {
+    let files: Vec<_> = files
+        .iter()
+        .map(|file| {
+            // The distinct producers, in the order they were first seen.
+            let mut producers = vec![];
+            for producer in file.producers() {
+                if !producers.contains(&producer) {
+                    producers.push(producer);
+                }
+            }
+
+            json!({
+                "file": file.path().to_string_lossy(),
+                "member": file.member(),
+                "arch": file.arch(),
+                "wasm_producers": file.wasm_producers().map(wasm_producers_json),
+                "wasm_producers_error": file.wasm_producers_error().map(|e| e.to_string()),
+                "toolchain_records": file
+                    .toolchain_records()
+                    .iter()
+                    .map(toolchain_record_json)
+                    .collect::<Vec<_>>(),
+                "units": file.units().map(unit_json).collect::<Vec<_>>(),
+                "producers": producers,
+                "errors": file.errors().map(|e| e.to_string()).collect::<Vec<_>>(),
+                "diagnostics": file
+                    .diagnostics()
+                    .iter()
+                    .map(diagnostic_json)
+                    .collect::<Vec<_>>(),
+            })
+        })
+        .collect();
+
+    let document = json!({
+        "schema_version": SCHEMA_VERSION,
+        "files": files,
+    });
+    println!("{}", serde_json::to_string_pretty(&document).unwrap());
+}
 
-    opts.producers(|producers| {
-        while let Some(producer) = producers.next()? {
-            println!("{}", producer);
+/// Print one JSON object per line for each unit, or for each error, in order.
+fn print_ndjson(files: &[ScannedFile]) {
+    for file in files {
+        let line = |key: &str, value: Value| {
+            let mut line = json!({
+                "schema_version": SCHEMA_VERSION,
+                "file": file.path().to_string_lossy(),
+                "member": file.member(),
+                "arch": file.arch(),
+            });
+            line[key] = value;
+            println!("{}", line);
+        };
+        if let Some(producers) = file.wasm_producers() {
+            line("wasm_producers", wasm_producers_json(producers));
+        }
+        if let Some(e) = file.wasm_producers_error() {
+            line("wasm_producers_error", Value::String(e.to_string()));
+        }
+        for record in file.toolchain_records() {
+            line("toolchain_record", toolchain_record_json(record));
+        }
+        for result in file.results() {
+            match *result {
+                Ok(ref unit) => line("unit", unit_json(unit)),
+                Err(ref e) => line("error", Value::String(e.to_string())),
+            }
         }
+        for diagnostic in file.diagnostics() {
+            line("diagnostic", diagnostic_json(diagnostic));
+        }
+    }
+}
+
+/// The file's path, followed by the archive member if it is a static library,
+/// like `ar` does, and the architecture if it is part of a universal binary,
+/// such as `libfoo.a(foo.o) (arm64)`.
+fn name(file: &ScannedFile) -> String {
+    let mut name = file.path().display().to_string();
+    if let Some(member) = file.member() {
+        name = format!("{}({})", name, member);
+    }
+    if let Some(arch) = file.arch() {
+        name = format!("{} ({})", name, arch);
+    }
+    name
+}
+
+fn unit_json(unit: &CompilationUnit) -> Value {
+    json!({
+        "kind": unit_kind_id(unit.kind()),
+        "offset": unit.offset(),
+        "version": unit.version(),
+        "address_size": unit.address_size(),
+        "name": unit.name(),
+        "comp_dir": unit.comp_dir(),
+        "language": unit.language(),
+        "language_name": unit.language_name(),
+        "producer": unit.producer().map(producer_json),
+    })
+}
+
+fn producer_json(producer: &Producer) -> Value {
+    json!({
+        "raw": producer.raw(),
+        "compiler": compiler_id(producer.compiler()),
+        "version": producer.version().map(|v| v.to_string()),
+        "rustc": producer.rustc().map(|rustc| json!({
+            "channel": channel_id(rustc.channel),
+            "commit_hash": rustc.commit_hash,
+            "commit_date": rustc.commit_date,
+        })),
+        "flags": producer.flags(),
+    })
+}
+
+fn wasm_producers_json(producers: &WasmProducers) -> Value {
+    let tools = |tools: &[WasmTool]| -> Vec<Value> {
+        tools
+            .iter()
+            .map(|tool| json!({ "name": tool.name, "version": tool.version }))
+            .collect()
+    };
+    json!({
+        "language": tools(producers.language()),
+        "processed_by": tools(producers.processed_by()),
+        "sdk": tools(producers.sdk()),
+    })
+}
+
+fn toolchain_record_json(record: &ToolchainRecord) -> Value {
+    json!({
+        "source": record.source().section_name(),
+        "text": record.text(),
+    })
+}
+
+fn unit_kind_id(kind: UnitKind) -> &'static str {
+    match kind {
+        UnitKind::Compile => "compile",
+        UnitKind::Type => "type",
+    }
+}
+
+fn diagnostic_json(diagnostic: &Diagnostic) -> Value {
+    json!({
+        "section": diagnostic.section(),
+        "offset": diagnostic.offset(),
+        "error": diagnostic.error().to_string(),
+    })
+}
 
-        Ok(())
-    })?
+/// Stable identifiers for compilers in the JSON output, which unlike their
+/// `Display` names are safe to match on.
+fn compiler_id(compiler: Compiler) -> &'static str {
+    match compiler {
+        Compiler::Gcc => "gcc",
+        Compiler::GnuAssembler => "gnu-as",
+        Compiler::Clang => "clang",
+        Compiler::AppleClang => "apple-clang",
+        Compiler::Flang => "flang",
+        Compiler::Rustc => "rustc",
+        Compiler::Go => "go",
+        Compiler::Swift => "swift",
+        Compiler::Intel => "intel",
+        Compiler::IntelFortran => "intel-fortran",
+        Compiler::Ldc => "ldc",
+        Compiler::Dmd => "dmd",
+        Compiler::Zig => "zig",
+        Compiler::Unknown => "unknown",
+    }
+}
+
+fn channel_id(channel: Channel) -> &'static str {
+    match channel {
+        Channel::Stable => "stable",
+        Channel::Beta => "beta",
+        Channel::Nightly => "nightly",
+        Channel::Dev => "dev",
+    }
 }
 
 fn parse_args() -> clap::ArgMatches<'static> {
@@ -29,9 +348,70 @@ fn parse_args() -> clap::ArgMatches<'static> {
         .version(env!("CARGO_PKG_VERSION"))
         .author(env!("CARGO_PKG_AUTHORS"))
         .about(env!("CARGO_PKG_DESCRIPTION"))
-        .arg(clap::Arg::with_name("file").required(true).help(
-            "The shared library or executable we should search for \
-             `DW_AT_producer` information in.",
-        ))
+        .arg(
+            clap::Arg::with_name("file")
+                .required(true)
+                .multiple(true)
+                .help(
+                    "The shared libraries or executables we should search for \
+                     `DW_AT_producer` information in. Directories are searched \
+                     recursively for object files. Use `-` to read a file from \
+                     standard input.",
+                ),
+        )
+        .arg(
+            clap::Arg::with_name("debug-dir")
+                .long("debug-dir")
+                .value_name("DIR")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .help(
+                    "A directory to search for separate debug info files, such as \
+                     /usr/lib/debug. May be given multiple times.",
+                ),
+        )
+        .arg(
+            clap::Arg::with_name("toolchain-fallback")
+                .long("toolchain-fallback")
+                .help(
+                    "For files without any DWARF, such as fully stripped binaries, \
+                     print the compiler and linker records in their `.comment` and \
+                     `.note.gnu.property` sections instead of an error, prefixed \
+                     with the section.",
+                ),
+        )
+        .arg(
+            clap::Arg::with_name("type-units")
+                .long("type-units")
+                .help(
+                    "Also report type units, from `.debug_types` sections and DWARF 5 \
+                     `DW_UT_type` units, whose producers are prefixed with `type unit: `.",
+                ),
+        )
+        .arg(
+            clap::Arg::with_name("lenient")
+                .long("lenient")
+                .help(
+                    "Carry on past units that can't be read, such as in damaged or \
+                     truncated files, skipping over corrupt unit headers. Such units \
+                     are reported as warnings with their offset, and don't cause a \
+                     non-zero exit status.",
+                ),
+        )
+        .arg(
+            clap::Arg::with_name("format")
+                .long("format")
+                .value_name("FORMAT")
+                .takes_value(true)
+                .possible_values(&["text", "json", "ndjson"])
+                .default_value("text")
+                .help(
+                    "How to print results: `text` prints one producer per line, \
+                     `json` prints a single document describing every compilation \
+                     unit, and `ndjson` prints one JSON object per compilation unit. \
+                     See the README for the JSON schema.",
+                ),
+        )
         .get_matches()
 }
@@ -0,0 +1,60 @@
+//! Loading input files, by memory mapping them where possible.
+
+use memmap2::Mmap;
+use std::fs;
+use std::io::{self, Read};
+use std::ops::Deref;
+use std::path::Path;
+
+/// The contents of an input file.
+///
+/// Regular files are memory mapped, so that only the pages holding the
+/// sections we actually parse are ever read from disk. Pipes, character
+/// devices, and anything else that can't be mapped are read into memory.
+#[derive(Debug)]
+pub enum Contents {
+    /// A memory mapped file.
+    Mapped(Mmap),
+    /// A file that was read into memory.
+    Read(Vec<u8>),
+}
+
+impl Deref for Contents {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match *self {
+            Contents::Mapped(ref mmap) => mmap,
+            Contents::Read(ref data) => data,
+        }
+    }
+}
+
+/// Load the file at the given path.
+pub fn load(path: &Path) -> io::Result<Contents> {
+    let mut file = fs::File::open(path)?;
+
+    let metadata = file.metadata()?;
+    if metadata.is_file() && metadata.len() > 0 {
+        // Safety: the mapping is only sound as long as nothing truncates or
+        // modifies the file while we're reading it. Like other tools that
+        // inspect binaries, we accept that risk in exchange for not reading
+        // multi-gigabyte files that we only need a few sections of.
+        if let Ok(mmap) = unsafe { Mmap::map(&file) } {
+            return Ok(Contents::Mapped(mmap));
+        }
+    }
+
+    let mut data = vec![];
+    file.read_to_end(&mut data)?;
+    Ok(Contents::Read(data))
+}
+
+/// Load the file at the given path, or return `None` if there is no such file.
+pub fn load_if_exists(path: &Path) -> io::Result<Option<Contents>> {
+    match load(path) {
+        Ok(contents) => Ok(Some(contents)),
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
@@ -0,0 +1,174 @@
+//! Locating separate debug info files for stripped binaries, via either their
+//! build ID or their `.gnu_debuglink` section, and the supplementary object
+//! files that `dwz` moves shared debug info into.
+
+use super::Result;
+use contents::{self, Contents};
+use crc32fast;
+use object::{self, Object, ObjectSection};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// Find and read the separate debug info file for `file`, which was read from
+/// `path`, or from memory, returning the debug info file's path and contents.
+///
+/// Candidates named by build ID are only accepted if they have the same build
+/// ID, and candidates named by `.gnu_debuglink` are only accepted if their
+/// CRC-32 matches the one recorded in the link.
+pub fn find_debug_file(
+    path: Option<&Path>,
+    file: &object::File,
+    debug_dirs: &[PathBuf],
+) -> Result<Option<(PathBuf, Contents)>> {
+    if let Some(build_id) = file.build_id()? {
+        for candidate in build_id_paths(build_id, debug_dirs) {
+            if let Some(data) = contents::load_if_exists(&candidate)? {
+                if has_build_id(&data, build_id) {
+                    return Ok(Some((candidate, data)));
+                }
+            }
+        }
+    }
+
+    if let Some((name, crc)) = file.gnu_debuglink()? {
+        let name = String::from_utf8_lossy(name);
+        for candidate in debuglink_paths(path, Path::new(&*name), debug_dirs)? {
+            if let Some(data) = contents::load_if_exists(&candidate)? {
+                if crc32fast::hash(&data) == crc {
+                    return Ok(Some((candidate, data)));
+                }
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+/// Find and read the supplementary object file for `file`, which was read from
+/// `path`, or from memory.
+///
+/// The supplementary file is named either by a `.gnu_debugaltlink` section, as
+/// written by `dwz`, or by a DWARF 5 `.debug_sup` section. Relative names are
+/// relative to the directory containing `path`, and are ignored for files read
+/// from memory. Files named by `.gnu_debugaltlink` must have the build ID
+/// recorded in the link, and are also looked up by that build ID within the
+/// debug directories.
+pub fn find_sup_file(
+    path: Option<&Path>,
+    file: &object::File,
+    debug_dirs: &[PathBuf],
+) -> Result<Option<Contents>> {
+    let dir = path.map(|path| path.parent().unwrap_or_else(|| Path::new("")));
+
+    if let Some((name, build_id)) = file.gnu_debugaltlink()? {
+        let name = String::from_utf8_lossy(name);
+        let mut candidates: Vec<_> = resolve(dir, Path::new(&*name)).into_iter().collect();
+        candidates.extend(build_id_paths(build_id, debug_dirs));
+        for candidate in candidates {
+            if let Some(data) = contents::load_if_exists(&candidate)? {
+                if has_build_id(&data, build_id) {
+                    return Ok(Some(data));
+                }
+            }
+        }
+        return Ok(None);
+    }
+
+    if let Some(name) = debug_sup_name(file)? {
+        if let Some(candidate) = resolve(dir, Path::new(&name)) {
+            return Ok(contents::load_if_exists(&candidate)?);
+        }
+    }
+
+    Ok(None)
+}
+
+/// Resolve `name` relative to `dir`, unless it is absolute. Relative names
+/// can't be resolved without a directory.
+fn resolve(dir: Option<&Path>, name: &Path) -> Option<PathBuf> {
+    if name.is_absolute() {
+        Some(name.into())
+    } else {
+        dir.map(|dir| dir.join(name))
+    }
+}
+
+/// Get the supplementary file name from a `.debug_sup` section, unless this is
+/// itself the supplementary file.
+fn debug_sup_name(file: &object::File) -> Result<Option<String>> {
+    let data = match file.section_by_name(".debug_sup") {
+        Some(section) => section.data()?,
+        None => return Ok(None),
+    };
+
+    // A 2-byte version, a 1-byte `is_supplementary` flag, and then the
+    // NUL-terminated file name.
+    if data.len() < 4 || data[2] != 0 {
+        return Ok(None);
+    }
+    let name = &data[3..];
+    let name = &name[..name.iter().position(|&b| b == 0).unwrap_or(name.len())];
+    if name.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(String::from_utf8_lossy(name).into_owned()))
+}
+
+/// The `.build-id/xx/yyyy.debug` paths within each debug directory.
+fn build_id_paths(build_id: &[u8], debug_dirs: &[PathBuf]) -> Vec<PathBuf> {
+    if build_id.len() < 2 {
+        return vec![];
+    }
+
+    let hex = |bytes: &[u8]| bytes.iter().map(|b| format!("{:02x}", b)).collect::<String>();
+    let relative = Path::new(".build-id")
+        .join(hex(&build_id[..1]))
+        .join(format!("{}.debug", hex(&build_id[1..])));
+
+    debug_dirs.iter().map(|dir| dir.join(&relative)).collect()
+}
+
+/// The places GDB looks for a `.gnu_debuglink` target: next to the binary, in
+/// a `.debug` subdirectory next to the binary, and within each debug directory
+/// either under the binary's absolute directory or at the top level. Binaries
+/// read from memory only have the top level of each debug directory.
+fn debuglink_paths(
+    path: Option<&Path>,
+    name: &Path,
+    debug_dirs: &[PathBuf],
+) -> Result<Vec<PathBuf>> {
+    let dir = match path.map(Path::parent) {
+        Some(Some(dir)) if dir != Path::new("") => Some(dir),
+        Some(_) => Some(Path::new(".")),
+        None => None,
+    };
+
+    let mut paths = vec![];
+    let mut absolute = None;
+    if let Some(dir) = dir {
+        paths.push(dir.join(name));
+        paths.push(dir.join(".debug").join(name));
+        absolute = Some(
+            fs::canonicalize(dir)?
+                .components()
+                .filter(|c| matches!(*c, Component::Normal(_)))
+                .collect::<PathBuf>(),
+        );
+    }
+
+    for debug_dir in debug_dirs {
+        if let Some(ref absolute) = absolute {
+            paths.push(debug_dir.join(absolute).join(name));
+        }
+        paths.push(debug_dir.join(name));
+    }
+
+    Ok(paths)
+}
+
+fn has_build_id(data: &[u8], build_id: &[u8]) -> bool {
+    match object::File::parse(data) {
+        Ok(file) => file.build_id().ok() == Some(Some(build_id)),
+        Err(_) => false,
+    }
+}
@@ -0,0 +1,82 @@
+//! Following `DW_TAG_imported_unit` entries into the partial units that `dwz`
+//! creates when deduplicating debug info, which may live in a supplementary
+//! object file.
+
+use super::{Error, Result};
+use reader::Reader;
+use gimli::{AttributeValue, DebugInfoOffset, Dwarf, Unit};
+use unit::RootAttrs;
+
+/// How many levels of partial units importing other partial units to follow.
+const MAX_IMPORT_DEPTH: usize = 8;
+
+/// Get the root entry attributes of the first partial unit imported by `unit`
+/// that has a `DW_AT_producer`.
+pub fn imported_attrs(
+    dwarf: &Dwarf<Reader>,
+    unit: &Unit<Reader>,
+) -> Result<Option<RootAttrs>> {
+    imported_attrs_at_depth(dwarf, unit, 0)
+}
+
+fn imported_attrs_at_depth(
+    dwarf: &Dwarf<Reader>,
+    unit: &Unit<Reader>,
+    depth: usize,
+) -> Result<Option<RootAttrs>> {
+    if depth >= MAX_IMPORT_DEPTH {
+        return Ok(None);
+    }
+
+    let mut tree = unit.entries_tree(None)?;
+    let root = tree.root()?;
+    let mut children = root.children();
+    while let Some(child) = children.next()? {
+        if child.entry().tag() != gimli::DW_TAG_imported_unit {
+            continue;
+        }
+
+        let attrs = match child.entry().attr_value(gimli::DW_AT_import)? {
+            Some(AttributeValue::DebugInfoRef(offset)) => partial_attrs(dwarf, offset, depth)?,
+            Some(AttributeValue::DebugInfoRefSup(offset)) => match dwarf.sup() {
+                Some(sup) => partial_attrs(sup, offset, depth)?,
+                None => return Err(Error::MissingSupplementaryFile),
+            },
+            _ => None,
+        };
+        if attrs.is_some() {
+            return Ok(attrs);
+        }
+    }
+
+    Ok(None)
+}
+
+/// Get the root entry attributes of the partial unit whose root entry is at the
+/// given offset, if it has a `DW_AT_producer`, or of the partial units it
+/// imports in turn.
+fn partial_attrs(
+    dwarf: &Dwarf<Reader>,
+    offset: DebugInfoOffset,
+    depth: usize,
+) -> Result<Option<RootAttrs>> {
+    let mut headers = dwarf.units();
+    while let Some(header) = headers.next()? {
+        let start = match header.offset().as_debug_info_offset() {
+            Some(start) => start,
+            None => continue,
+        };
+        if offset.0 < start.0 || offset.0 >= start.0 + header.length_including_self() {
+            continue;
+        }
+
+        let unit = dwarf.unit(header)?;
+        let attrs = RootAttrs::read(dwarf, &unit)?;
+        if attrs.producer.is_some() {
+            return Ok(Some(attrs));
+        }
+        return imported_attrs_at_depth(dwarf, &unit, depth + 1);
+    }
+
+    Err(Error::MissingImportedUnit(offset.0 as u64))
+}
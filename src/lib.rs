@@ -21,24 +21,25 @@ First, add this to your `Cargo.toml`:
 version = "0.1.0"
 # Do not build the command line `dwprod` executable.
 default-features = false
+# But do support compressed debug sections.
+features = ["compression"]
 ```
 
-Then, import the `dwprod` crate and use it to iterate over `DW_AT_producer`
-values:
+Then, import the `dwprod` crate and use it to load a file's debug info and
+iterate over its `DW_AT_producer` values:
 
 ```rust,no_run
 extern crate dwprod;
 
 fn try_main() -> dwprod::Result<()> {
-    let opts = dwprod::Options::new("path/to/some/executable");
+    let info = dwprod::Options::new("path/to/some/executable").load()?;
 
-    opts.producers(|producers| {
-        while let Some(producer) = producers.next()? {
-            println!("Found DW_AT_producer = {}", producer);
-        }
+    let mut producers = info.producers();
+    while let Some(producer) = producers.next()? {
+        println!("Found DW_AT_producer = {}", producer);
+    }
 
-        Ok(())
-    })?
+    Ok(())
 }
 
 fn main() {
@@ -49,6 +50,78 @@ fn main() {
 }
 ```
 
+A `DebugInfo` owns the data it was loaded from, so it can be stored in a
+struct or returned from a function, and iterated over as many times as needed.
+Cloning it is cheap, and it can be sent to other threads.
+
+Binaries that are already in memory, such as ones downloaded from a blob store,
+can be inspected with `Options::from_bytes` or `Options::from_vec`, and ones
+from any `std::io::Read` with `Options::from_reader`.
+
+Mach-O binaries are supported too: a stripped binary's debug info is found in
+the `.dSYM` bundle next to it, and a `.dSYM` bundle can also be given directly.
+Universal binaries hold a binary for each architecture, which
+`Options::load_all` loads separately, and `DebugInfo::arch` tells apart. So
+are the PE images and COFF object files that MinGW and Cygwin put DWARF in.
+
+Static libraries are `ar` archives of object files, in either the GNU or the BSD
+variant, and `Options::load_all` loads each of their members, which
+`DebugInfo::member` names. The members of thin archives are read from the files
+they refer to. Rust's `.rlib`s are static libraries too, whose crate metadata
+member is skipped. So are members without any DWARF, unless
+`Options::toolchain_fallback` is enabled. Like other unlinked ELF `.o` files,
+the members' debug sections have their relocations applied before they are
+read.
+
+Files without any DWARF, such as fully stripped release binaries, are an error,
+unless `Options::toolchain_fallback` is enabled. Then they load without any
+compilation units, and `DebugInfo::toolchain_records` has the compiler and
+linker strings from their `.comment` section, such as `GCC: (GNU) 12.2.0`, and
+the properties in their `.note.gnu.property` section, each labelled by where it
+came from.
+
+WebAssembly modules keep their DWARF in custom sections, which are read in the
+same way. Their standard `producers` section, which lists the source languages,
+tools and SDKs that built the module, is available from
+`DebugInfo::wasm_producers`, even for release builds without any DWARF. A
+malformed `producers` section is treated as if there were none, and the error
+parsing it is available from `DebugInfo::wasm_producers_error`. Modules without
+either are an error, unless `Options::toolchain_fallback` is enabled, in which
+case they load with nothing to report.
+
+Type units, which hold the type definitions that several compilation units
+share, are left out by default, as there are often many more of them than
+compilation units. With `Options::type_units` enabled, the type units in DWARF 4
+`.debug_types` sections and DWARF 5 `DW_UT_type` units are included too, and
+`CompilationUnit::kind` tells them apart.
+
+Units in the 64-bit DWARF format, which debug info larger than 4 GiB needs, are
+read just like the usual 32-bit ones, even when both formats are mixed within
+one file, and so are units for targets with 32-bit addresses, such as armv7 and
+i686.
+
+A unit that can't be read is an error from the `CompilationUnits` and
+`Producers` iterators, after which they carry on with the next unit, but a
+corrupt unit header ends its section. For forensic work on damaged or truncated
+files, `Options::lenient` skips over corrupt unit headers instead, and records
+the errors for each unit as `Diagnostic`s, with the unit's offset, rather than
+returning them.
+
+The variants of `dwprod::Error` tell apart files in formats that `dwprod`
+doesn't understand at all, as `Error::UnsupportedFormat`, missing sections, such
+as `.debug_info` in files without any debug info, as `Error::MissingSection`,
+attributes encoded with forms that can't be read, as `Error::UnsupportedForm`,
+and universal binary architectures and archive members that extend past the end
+of a truncated file, as `Error::OutOfBounds`. A `dwz` supplementary object file
+that can't be found is an `Error::MissingSupplementaryFile`, and an import of an
+offset outside of any unit is an `Error::MissingImportedUnit`. A split DWARF
+`.dwo` file that can't be read, or that lacks the skeleton unit's split unit, is
+an `Error::SplitDwarfFile` or `Error::MissingSplitUnit`, with the `.dwo` file's
+path. A `.dSYM` bundle without a DWARF file is an `Error::MissingDsymFile`, and
+using `Options::load` on a file with several objects is an
+`Error::MultipleObjects`. Errors reading a particular unit are wrapped in an
+`Error::Unit`, with the path of the file and the offset of the unit.
+
 The [`fallible-iterator`](https://crates.io/crates/fallible-iterator) crate can
 also be used to leverage iterator combinators like `map` and `filter`:
 
@@ -59,29 +132,89 @@ extern crate fallible_iterator;
 use fallible_iterator::FallibleIterator;
 use std::path::Path;
 
-fn each_rustc_producer<F>(
-    shared_lib_or_exe: &Path,
-    mut callback: F
-) -> dwprod::Result<()>
-where
-    F: FnMut(String)
-{
-    let opts = dwprod::Options::new(shared_lib_or_exe);
-    opts.producers(|producers| {
-        producers
-            // Filter down to only the producers with "rustc" in their name.
-            .filter(|p| p.contains("rustc"))
-            // Then map the given callback over each producer.
-            .map(&mut callback)
-            // Finally, use `count` to force iteration.
-            .count()?;
-
-        Ok(())
-    })?
+fn rustc_producers(shared_lib_or_exe: &Path) -> dwprod::Result<Vec<String>> {
+    let info = dwprod::Options::new(shared_lib_or_exe).load()?;
+    info.producers()
+        // Filter down to only the producers with "rustc" in their name.
+        .filter(|p| p.contains("rustc"))
+        .collect()
 }
 # fn main() {}
 ```
 
+Producer strings can be parsed into their compiler family, version, and flags
+with `Producer::parse`:
+
+```rust
+extern crate dwprod;
+
+use dwprod::{Channel, Compiler, Producer};
+
+# fn main() {
+let producer = Producer::parse(
+    "clang LLVM (rustc version 1.22.0-nightly (088216fb9 2017-09-04))"
+);
+assert_eq!(producer.compiler(), Compiler::Rustc);
+assert_eq!(producer.version().unwrap().to_string(), "1.22.0-nightly");
+assert_eq!(producer.rustc().unwrap().channel, Channel::Nightly);
+
+let producer = Producer::parse("GNU C 4.8.5 -m64 -O2");
+assert_eq!(producer.compiler(), Compiler::Gcc);
+assert_eq!(producer.flags(), ["-m64", "-O2"]);
+# }
+```
+
+To find out which source file was built with which compiler, iterate over
+`CompilationUnit` records instead, which also include each unit's
+`.debug_info` offset, DWARF version, address size, `DW_AT_comp_dir`, and
+`DW_AT_language`:
+
+```rust,no_run
+extern crate dwprod;
+
+fn try_main() -> dwprod::Result<()> {
+    let info = dwprod::Options::new("path/to/some/executable").load()?;
+
+    let mut units = info.compilation_units();
+    while let Some(unit) = units.next()? {
+        println!(
+            "{} was compiled by {}",
+            unit.name().unwrap_or("<unknown>"),
+            unit.producer().map_or("<unknown>".into(), |p| p.to_string())
+        );
+    }
+
+    Ok(())
+}
+# fn main() { try_main().unwrap(); }
+```
+
+To inspect many files, or every object file within some directories, use
+`Scan`, which collects the results for each file, including any errors. With
+the `rayon` feature enabled, files and the compilation units within them are
+inspected in parallel, and the results are returned in the same order as
+without it:
+
+```rust,no_run
+extern crate dwprod;
+
+# fn main() {
+let files = dwprod::Scan::new()
+    .path("path/to/sysroot")
+    .path("path/to/some/executable")
+    .run();
+
+for file in &files {
+    for producer in file.producers() {
+        println!("{}: {}", file.path().display(), producer);
+    }
+    for error in file.errors() {
+        eprintln!("{}: {}", file.path().display(), error);
+    }
+}
+# }
+```
+
 #### As a Command Line Tool
 
 First, install via `cargo`:
@@ -121,29 +254,168 @@ GNU C 4.8.5 -m64 -mtune=generic -march=x86-64 -g3 -O3 -O2 -O2 -std=gnu11 -fvisib
 <truncated>
 ```
 
+Any number of files can be given at once, and directories are searched
+recursively for ELF, Mach-O, PE, COFF, and WebAssembly files and static
+libraries, skipping anything else. The DWARF in a `.dSYM` bundle next to its
+binary is reported with the binary, rather than a second time on its own. Split
+DWARF `.dwo` files and `.dwp` packages are only read through the binaries they
+belong to. A file named `-` is read from standard input, and reported as
+`<stdin>`; it can only be given once. When there could be more than one file,
+each line is prefixed with the file it came from. Each member of a static
+library and each architecture of a universal binary is reported separately, and
+always prefixed, as `archive(member)` and `path (arch)`. The tools in a
+WebAssembly module's `producers` section are printed after its
+`DW_AT_producer`s, as lines like `processed-by: rustc 1.75.0`, and a malformed
+`producers` section is only warned about. With `--toolchain-fallback`, files
+without any DWARF print their toolchain records instead of an error, prefixed
+with the section they came from, as lines like `.comment: GCC: (GNU) 12.2.0`.
+With `--type-units`, the producers of type units are printed too, prefixed with
+`type unit: `:
+
+```commands
+$ dwprod /usr/lib/debug /opt/app/bin/app
+/usr/lib/debug/.build-id/3c/2b1d7a4e.debug: GNU C17 12.2.0 -mtune=generic -march=x86-64 -g -O2
+/opt/app/bin/app: clang LLVM (rustc version 1.75.0 (82e1608df 2023-12-21))
+```
+
+To consume `dwprod`'s output from other tools, pass `--format json` to get a
+single JSON document for all of the files, or `--format ndjson` to get one
+JSON object per line for each compilation unit:
+
+```commands
+$ dwprod --format ndjson path/to/executable
+{"file":"path/to/executable","schema_version":2,"unit":{"address_size":8,"comp_dir":"/home/user/app","kind":"compile","language":28,"language_name":"DW_LANG_Rust","name":"src/main.rs","offset":0,"producer":{"compiler":"rustc","flags":[],"raw":"clang LLVM (rustc version 1.75.0 (82e1608df 2023-12-21))","rustc":{"channel":"stable","commit_date":"2023-12-21","commit_hash":"82e1608df"},"version":"1.75.0"},"version":4}}
+```
+
+The `--format json` document has these fields:
+
+* `schema_version`: The version of this schema, currently `2`. It changes
+  whenever a field is removed or changes meaning, but not when fields are
+  added.
+* `files`: An array of file objects, in the order the files were given on the
+  command line, with the contents of each directory sorted by path.
+
+File objects have these fields:
+
+* `file`: The path that was inspected.
+* `member`: The static library member that was inspected, such as `"foo.o"`,
+  or `null`.
+* `arch`: The architecture of the universal binary slice that was inspected,
+  such as `"arm64"`, or `null`.
+* `units`: An array of compilation unit objects, described below.
+* `producers`: An array of the distinct `DW_AT_producer` strings, in the order
+  they were first seen.
+* `errors`: An array of error messages for the file, if it couldn't be read at
+  all, or for each compilation unit that couldn't be read.
+* `wasm_producers`: For WebAssembly modules with a `producers` section, an
+  object with `language`, `processed_by` and `sdk` arrays, each of objects with
+  a tool's `name` and `version`. Otherwise `null`.
+* `wasm_producers_error`: For WebAssembly modules with a malformed `producers`
+  section, the error message parsing it. Otherwise `null`.
+* `toolchain_records`: With `--toolchain-fallback`, for files without any
+  DWARF, an array of objects with the `source` section of each toolchain
+  record, such as `".comment"`, and its `text`.
+* `diagnostics`: With `--lenient`, an array of objects for the units that
+  couldn't be read, with the `section` and `offset` of each unit, and the
+  `error` message.
+
+Each `--format ndjson` line has `schema_version`, `file`, `member` and `arch`
+fields, and either a `unit` field with a compilation unit object, or an `error`
+field with an error message. WebAssembly modules with a `producers` section also
+get a line with a `wasm_producers` field, or a `wasm_producers_error` field with
+an error message if it is malformed, before their units, each toolchain record
+gets a line with a `toolchain_record` field, and with `--lenient`, each unit
+that couldn't be read gets a line with a `diagnostic` field, after the units.
+
+Compilation unit objects have these fields, any of which other than `kind`,
+`offset`, `version` and `address_size` may be `null`:
+
+* `kind`: `"compile"` for compilation units, or `"type"` for the type units
+  included by `--type-units`.
+* `offset`: The offset of the unit within the `.debug_info` section, or within
+  the `.debug_types` section for DWARF 4 type units.
+* `version`: The unit's DWARF version.
+* `address_size`: The size of a target address, in bytes.
+* `name`: The unit's `DW_AT_name`, usually its primary source file.
+* `comp_dir`: The unit's `DW_AT_comp_dir`.
+* `language`: The unit's `DW_AT_language` code.
+* `language_name`: The name of that code, such as `"DW_LANG_C11"`.
+* `producer`: An object with these fields:
+  * `raw`: The `DW_AT_producer` string.
+  * `compiler`: One of `"gcc"`, `"gnu-as"`, `"clang"`, `"apple-clang"`,
+    `"flang"`, `"rustc"`, `"go"`, `"swift"`, `"intel"`, `"intel-fortran"`,
+    `"ldc"`, `"dmd"`, `"zig"` or `"unknown"`.
+  * `version`: The compiler version, such as `"12.2.0"` or `"1.75.0-nightly"`.
+  * `rustc`: For `rustc`, an object with a `channel` of `"stable"`, `"beta"`,
+    `"nightly"` or `"dev"`, and the `commit_hash` and `commit_date` of the
+    compiler.
+  * `flags`: An array of the command line flags recorded in the producer.
+
+When any file or compilation unit can't be read, `dwprod` still prints the
+others, but exits with a non-zero status. With `--lenient`, units that can't be
+read, including those with corrupt headers, are printed as warnings with their
+offset, such as `Warning: unit at .debug_info+0x1e: Found an unknown DWARF
+version`, and don't affect the exit status.
+
 For more details about the `dwprod` command line tool, run `dwprod --help`.
  */
 
 #![deny(missing_docs)]
 #![deny(missing_debug_implementations)]
 
+extern crate crc32fast;
 extern crate fallible_iterator;
 extern crate gimli;
+extern crate memmap2;
 extern crate object;
+#[cfg(feature = "rayon")]
+extern crate rayon;
+
+mod archive;
+mod contents;
+mod debuglink;
+mod dwz;
+mod macho;
+mod producer;
+mod reader;
+mod reloc;
+mod scan;
+mod split;
+mod toolchain;
+mod unit;
+mod wasm;
 
+pub use producer::{Channel, Compiler, Producer, RustcInfo, Version};
+pub use scan::{Scan, ScannedFile};
+pub use toolchain::{RecordSource, ToolchainRecord};
+pub use unit::{CompilationUnit, Diagnostic, UnitKind};
+pub use wasm::{WasmProducers, WasmTool};
+
+use contents::Contents;
 use fallible_iterator::FallibleIterator;
-use gimli::{CompilationUnitHeadersIter, DebugAbbrev, DebugInfo, DebugStr, EndianBuf, NativeEndian,
-            Reader};
+use gimli::{
+    DebugInfoOffset, DebugTypes, DebugTypesOffset, Dwarf, DwarfPackage, Reader as _,
+    RunTimeEndian, Section, SectionId, Unit, UnitHeader, UnitSectionOffset,
+};
+use object::{Object, ObjectSection};
+use reader::{Data, Reader};
+use std::borrow::Cow;
 use std::error;
 use std::fmt;
-use std::fs;
-use std::io::{self, Read};
+use std::io;
+use std::mem;
+use std::ops::Range;
 use std::path;
+use std::sync::Arc;
+use unit::RootAttrs;
 
 /// Errors that `dwprod` can encounter.
 #[derive(Debug)]
 pub enum Error {
-    /// Some other error.
+    /// Input that is malformed in a way that has no more specific variant,
+    /// such as a WebAssembly module with an invalid header or name, or a
+    /// relocation outside of its section. Nothing but the message is known
+    /// about these.
     Msg(String),
 
     /// An IO error.
@@ -151,6 +423,94 @@ pub enum Error {
 
     /// A DWARF parsing error.
     Dwarf(gimli::Error),
+
+    /// An object file parsing error.
+    Object(object::Error),
+
+    /// The file isn't in any format that `dwprod` understands, such as a text
+    /// file, or a WebAssembly module of a version other than 1. This says
+    /// what was found instead.
+    UnsupportedFormat(String),
+
+    /// A section that is needed is missing, such as `.debug_info` in a file
+    /// without any debug info, or `.debug_str` in a file with units that refer
+    /// to it. This is the section's name.
+    MissingSection(String),
+
+    /// Debug info deduplicated by `dwz` refers to strings or partial units in
+    /// a supplementary object file, named by its `.gnu_debugaltlink` or
+    /// `.debug_sup` section, that couldn't be found.
+    MissingSupplementaryFile,
+
+    /// A `DW_TAG_imported_unit` in debug info deduplicated by `dwz` imports an
+    /// offset that isn't within any unit. This is the offset within the
+    /// `.debug_info` of the file it refers to.
+    MissingImportedUnit(u64),
+
+    /// The split DWARF `.dwo` file named by a skeleton unit couldn't be read,
+    /// such as because it doesn't exist.
+    SplitDwarfFile {
+        /// The path of the `.dwo` file.
+        path: path::PathBuf,
+        /// The error encountered reading it.
+        error: io::Error,
+    },
+
+    /// The split DWARF `.dwo` file named by a skeleton unit doesn't have a
+    /// split unit with the skeleton unit's ID.
+    MissingSplitUnit {
+        /// The path of the `.dwo` file.
+        path: path::PathBuf,
+        /// The skeleton unit's ID.
+        dwo_id: u64,
+    },
+
+    /// A `.dSYM` bundle's `Contents/Resources/DWARF` directory has neither a
+    /// file named after the bundle nor exactly one other file. This is the
+    /// directory.
+    MissingDsymFile(path::PathBuf),
+
+    /// `Options::load` was used on a universal binary with more than one
+    /// architecture, or a static library with more than one member with
+    /// DWARF, which need `Options::load_all` instead. This is the number of
+    /// objects.
+    MultipleObjects(usize),
+
+    /// An attribute is encoded with a form that `dwprod` can't read it from.
+    UnsupportedForm {
+        /// The attribute, or `None` if the form isn't known at all, in which
+        /// case no attributes of the entry it is in can be read.
+        attribute: Option<gimli::DwAt>,
+        /// The form.
+        form: gimli::DwForm,
+    },
+
+    /// An error reading one of the units of a file.
+    ///
+    /// This is displayed with the unit's location within the file, but not
+    /// the file's path, which callers usually report alongside it already.
+    Unit {
+        /// The path of the file, or `None` if it was loaded from memory.
+        path: Option<path::PathBuf>,
+        /// The section that the unit is in, which is `.debug_info`, or
+        /// `.debug_types` for DWARF 4 type units.
+        section: &'static str,
+        /// The offset of the unit's header within `section`.
+        offset: u64,
+        /// The error encountered reading the unit.
+        error: Box<Error>,
+    },
+
+    /// A universal binary's architecture, or an archive's member, extends
+    /// past the end of the file, which is truncated or corrupt.
+    OutOfBounds {
+        /// What is out of bounds: `"fat arch"` or `"archive member"`.
+        what: &'static str,
+        /// The offset of its data within the file.
+        offset: u64,
+        /// The size of its data.
+        size: u64,
+    },
 }
 
 impl fmt::Display for Error {
@@ -159,24 +519,73 @@ impl fmt::Display for Error {
             Error::Msg(ref s) => write!(f, "{}", s),
             Error::Io(ref e) => write!(f, "{}", e),
             Error::Dwarf(ref e) => write!(f, "{}", e),
+            Error::Object(ref e) => write!(f, "{}", e),
+            Error::UnsupportedFormat(ref found) => write!(f, "unsupported file format: {}", found),
+            Error::MissingSection(ref name) => write!(f, "missing {} section", name),
+            Error::MissingSupplementaryFile => write!(f, "missing supplementary object file"),
+            Error::MissingImportedUnit(offset) => {
+                write!(f, "no unit at .debug_info offset {:#x}", offset)
+            }
+            Error::SplitDwarfFile {
+                ref path,
+                ref error,
+            } => write!(f, "failed to read split DWARF file {}: {}", path.display(), error),
+            Error::MissingSplitUnit { ref path, dwo_id } => write!(
+                f,
+                "no split unit with DWO ID {:#x} in {}",
+                dwo_id,
+                path.display()
+            ),
+            Error::MissingDsymFile(ref dir) => {
+                write!(f, "no DWARF file found in {}", dir.display())
+            }
+            Error::MultipleObjects(count) => write!(
+                f,
+                "universal binary or archive with {} objects; use `Options::load_all` to load \
+                 each of them",
+                count
+            ),
+            Error::UnsupportedForm {
+                attribute: Some(attribute),
+                form,
+            } => write!(f, "unsupported form {} for {}", form, attribute),
+            Error::UnsupportedForm {
+                attribute: None,
+                form,
+            } => write!(f, "unsupported attribute form {}", form),
+            Error::Unit {
+                section,
+                offset,
+                ref error,
+                ..
+            } => write!(f, "unit at {}+{:#x}: {}", section, offset, error),
+            Error::OutOfBounds { what, offset, size } => write!(
+                f,
+                "{} at offset {:#x} with size {:#x} extends past the end of the file",
+                what, offset, size
+            ),
         }
     }
 }
 
 impl error::Error for Error {
-    fn description(&self) -> &str {
-        match *self {
-            Error::Msg(ref s) => s.as_str(),
-            Error::Io(ref e) => e.description(),
-            Error::Dwarf(ref e) => e.description(),
-        }
-    }
-
-    fn cause(&self) -> Option<&error::Error> {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
         match *self {
-            Error::Msg(_) => None,
             Error::Io(ref e) => Some(e),
             Error::Dwarf(ref e) => Some(e),
+            Error::Object(ref e) => Some(e),
+            Error::Unit { ref error, .. } => Some(&**error),
+            Error::SplitDwarfFile { ref error, .. } => Some(error),
+            Error::Msg(_)
+            | Error::UnsupportedFormat(_)
+            | Error::MissingSection(_)
+            | Error::MissingSupplementaryFile
+            | Error::MissingImportedUnit(_)
+            | Error::MissingSplitUnit { .. }
+            | Error::MissingDsymFile(_)
+            | Error::MultipleObjects(_)
+            | Error::UnsupportedForm { .. }
+            | Error::OutOfBounds { .. } => None,
         }
     }
 }
@@ -189,7 +598,19 @@ impl From<io::Error> for Error {
 
 impl From<gimli::Error> for Error {
     fn from(e: gimli::Error) -> Error {
-        Error::Dwarf(e)
+        match e {
+            gimli::Error::UnknownForm(form) => Error::UnsupportedForm {
+                attribute: None,
+                form,
+            },
+            e => Error::Dwarf(e),
+        }
+    }
+}
+
+impl From<object::Error> for Error {
+    fn from(e: object::Error) -> Error {
+        Error::Object(e)
     }
 }
 
@@ -211,7 +632,33 @@ pub type Result<T> = ::std::result::Result<T, Error>;
 /// A builder for configuring `dwprod`.
 #[derive(Default, Debug)]
 pub struct Options {
-    file: path::PathBuf,
+    input: Input,
+    debug_dirs: Vec<path::PathBuf>,
+    toolchain_fallback: bool,
+    type_units: bool,
+    lenient: bool,
+}
+
+/// Where the shared library or executable to inspect comes from.
+enum Input {
+    Path(path::PathBuf),
+    Data(Vec<u8>),
+}
+
+impl Default for Input {
+    fn default() -> Input {
+        Input::Path(path::PathBuf::new())
+    }
+}
+
+impl fmt::Debug for Input {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Input::Path(ref path) => f.debug_tuple("Path").field(path).finish(),
+            // Don't dump the whole binary.
+            Input::Data(ref data) => write!(f, "Data({} bytes)", data.len()),
+        }
+    }
 }
 
 impl Options {
@@ -219,96 +666,924 @@ impl Options {
     /// given path.
     pub fn new<P: AsRef<path::Path>>(path: P) -> Options {
         Options {
-            file: path.as_ref().into(),
+            input: Input::Path(path.as_ref().into()),
+            debug_dirs: vec![],
+            toolchain_fallback: false,
+            type_units: false,
+            lenient: false,
+        }
+    }
+
+    /// Construct a new `Options` for the shared library or executable with the
+    /// given contents, which are copied.
+    ///
+    /// Without a path, separate debug info files can only be found by build ID
+    /// or absolute path within the debug search directories, and there is no
+    /// `.dwp` package to look for next to the file. Split units' `.dwo` files
+    /// are still found through their `DW_AT_comp_dir`.
+    pub fn from_bytes(data: &[u8]) -> Options {
+        Options::from_vec(data.to_vec())
+    }
+
+    /// Construct a new `Options` for the shared library or executable with the
+    /// given contents, without copying them. See `from_bytes`.
+    pub fn from_vec(data: Vec<u8>) -> Options {
+        Options {
+            input: Input::Data(data),
+            debug_dirs: vec![],
+            toolchain_fallback: false,
+            type_units: false,
+            lenient: false,
         }
     }
 
+    /// Construct a new `Options` for the shared library or executable read
+    /// from the given reader, such as standard input. The reader is read to
+    /// the end right away. See `from_bytes`.
+    pub fn from_reader<R: io::Read>(mut reader: R) -> Result<Options> {
+        let mut data = vec![];
+        reader.read_to_end(&mut data)?;
+        Ok(Options::from_vec(data))
+    }
+
+    /// Add a directory to search for separate debug info files, such as
+    /// `/usr/lib/debug`.
+    ///
+    /// When the configured file has no `.debug_info` section of its own, the
+    /// matching debug info file is located by build ID (in
+    /// `.build-id/xx/yyyy.debug` within each search directory) or by the name
+    /// in its `.gnu_debuglink` section. The directory containing the file and
+    /// its `.debug` subdirectory are always searched for `.gnu_debuglink`
+    /// targets.
+    pub fn debug_search_dir<P: AsRef<path::Path>>(mut self, dir: P) -> Options {
+        self.debug_dirs.push(dir.as_ref().into());
+        self
+    }
+
+    /// Whether to fall back to the toolchain records outside of DWARF, in the
+    /// `.comment` and `.note.gnu.property` sections, for files without any
+    /// DWARF. The default is not to, in which case such files are an error.
+    ///
+    /// With the fallback, such files load with no compilation units, and
+    /// `DebugInfo::toolchain_records` gives some idea of what built them
+    /// instead. Files with DWARF are unaffected. WebAssembly modules have no
+    /// such sections, and their `producers` section is read regardless, so
+    /// with the fallback, modules without either DWARF or a `producers`
+    /// section load with nothing to report, rather than being an error.
+    pub fn toolchain_fallback(mut self, fallback: bool) -> Options {
+        self.toolchain_fallback = fallback;
+        self
+    }
+
+    /// Whether to include type units, which is not the default.
+    ///
+    /// Type units hold a single type each, which the compilation units that
+    /// use it refer to by signature, so that the linker can drop duplicates.
+    /// Some toolchains give them their own `DW_AT_producer`, but as there are
+    /// often many more of them than compilation units, they would inflate the
+    /// counts of most reports. With this enabled, DWARF 5 `DW_UT_type` units
+    /// are yielded among the compilation units in `.debug_info`, the type
+    /// units of DWARF 4 `.debug_types` sections are yielded after them, and
+    /// `CompilationUnit::kind` tells them apart.
+    pub fn type_units(mut self, type_units: bool) -> Options {
+        self.type_units = type_units;
+        self
+    }
+
+    /// Whether to carry on past units that can't be read, which is not the
+    /// default.
+    ///
+    /// This is meant for forensic work on damaged or truncated files. When
+    /// lenient, the errors encountered reading each unit are recorded as
+    /// `Diagnostic`s, with the unit's offset, instead of being returned by
+    /// the `CompilationUnits` and `Producers` iterators, which then carry on
+    /// with the next unit. A unit whose header is corrupt is skipped using
+    /// its length, rather than ending the iteration of its section. See
+    /// `CompilationUnits::diagnostics`.
+    pub fn lenient(mut self, lenient: bool) -> Options {
+        self.lenient = lenient;
+        self
+    }
+
+    /// Finish configuring and load the configured file's debug info.
+    ///
+    /// The returned `DebugInfo` owns the file's data, so unlike with the
+    /// `producers` and `compilation_units` methods, it can be stored in a
+    /// struct or returned from a function, and iterated over later.
+    ///
+    /// Universal binaries with more than one architecture, and archives with
+    /// other than one member with DWARF, are an error; use `load_all` for
+    /// them.
+    pub fn load(self) -> Result<DebugInfo> {
+        let mut all = self.load_all()?;
+        match all.len() {
+            0 => Err(Error::MissingSection(".debug_info".into())),
+            1 => Ok(all.pop().unwrap()),
+            count => Err(Error::MultipleObjects(count)),
+        }
+    }
+
+    /// Finish configuring and load the debug info of each object within the
+    /// configured file.
+    ///
+    /// This is a single object for most files, but universal ("fat") Mach-O
+    /// binaries have one for each architecture, and static libraries have one
+    /// for each member with DWARF, in order. Members without any DWARF are
+    /// skipped, unless `toolchain_fallback` is enabled. If any of the objects
+    /// can't be loaded, that is an error for the whole file.
+    pub fn load_all(self) -> Result<Vec<DebugInfo>> {
+        self.load_each()?
+            .into_iter()
+            .map(|loaded| loaded.result)
+            .collect()
+    }
+
+    /// Load the debug info of each object within the configured file, like
+    /// `load_all`, but carrying on past the objects that can't be loaded.
+    fn load_each(mut self) -> Result<Vec<Loaded>> {
+        let (path, contents) = match mem::take(&mut self.input) {
+            Input::Path(path) => {
+                // A `.dSYM` bundle is a directory, with the debug info in a
+                // file within it.
+                let contents = if macho::is_dsym_bundle(&path) {
+                    contents::load(&macho::bundle_dwarf_file(&path)?)?
+                } else {
+                    contents::load(&path)?
+                };
+                (Some(path), contents)
+            }
+            Input::Data(data) => (None, Contents::Read(data)),
+        };
+        let data = Data::new(contents);
+
+        let mut loaded = vec![];
+        match macho::fat_arches(&data)? {
+            Some(arches) => {
+                for arch in arches {
+                    self.load_slice(&path, &data, arch.range, Some(arch.name), &mut loaded)?;
+                }
+            }
+            None => self.load_slice(&path, &data, 0..data.len(), None, &mut loaded)?,
+        }
+        Ok(loaded)
+    }
+
+    /// Load the debug info of the object at `range` within `data`, which was
+    /// read from `path`, or if it is an archive, of each of its members.
+    fn load_slice(
+        &self,
+        path: &Option<path::PathBuf>,
+        data: &Data,
+        range: Range<usize>,
+        arch: Option<String>,
+        loaded: &mut Vec<Loaded>,
+    ) -> Result<()> {
+        let bytes = &data[range.clone()];
+        if !archive::is_archive(bytes) {
+            let result = self.load_object(path.clone(), data, range, arch.clone());
+            loaded.push(Loaded {
+                member: None,
+                arch,
+                result,
+            });
+            return Ok(());
+        }
+
+        for member in archive::members(bytes)? {
+            let result = match member.location {
+                archive::Location::Within(ref within) => {
+                    let start = range.start + within.start;
+                    let end = range.start + within.end;
+                    self.load_object(path.clone(), data, start..end, arch.clone())
+                }
+                archive::Location::Thin(ref name) => {
+                    self.load_thin_member(path.as_deref(), name, arch.clone())
+                }
+            };
+            // Static libraries often mix objects built with and without
+            // debug info, such as hand-written assembly or a prebuilt C
+            // library's objects, and there is nothing to report for them.
+            if let Err(Error::MissingSection(ref name)) = result {
+                if name == ".debug_info" {
+                    continue;
+                }
+            }
+            let result = result.map(|mut info| {
+                info.member = Some(member.name.clone());
+                info
+            });
+            loaded.push(Loaded {
+                member: Some(member.name),
+                arch: arch.clone(),
+                result,
+            });
+        }
+        Ok(())
+    }
+
+    /// Load the debug info of a thin archive's member, which is the file
+    /// `name` relative to the archive at `archive`.
+    fn load_thin_member(
+        &self,
+        archive: Option<&path::Path>,
+        name: &path::Path,
+        arch: Option<String>,
+    ) -> Result<DebugInfo> {
+        let archive = archive.ok_or("the members of thin archives read from memory can't be found")?;
+        let path = archive.parent().unwrap_or_else(|| path::Path::new("")).join(name);
+        let data = Data::new(contents::load(&path)?);
+        let range = 0..data.len();
+        self.load_object(Some(path), &data, range, arch)
+    }
+
+    /// Load the debug info of the object at `range` within `data`, which was
+    /// read from `path`, and is for the architecture `arch` of a universal
+    /// binary.
+    fn load_object(
+        &self,
+        path: Option<path::PathBuf>,
+        data: &Data,
+        range: Range<usize>,
+        arch: Option<String>,
+    ) -> Result<DebugInfo> {
+        let bytes = &data[range.clone()];
+        if wasm::is_wasm(bytes) {
+            return load_wasm(path, data, range, self.toolchain_fallback).map(|info| DebugInfo {
+                type_units: self.type_units,
+                lenient: self.lenient,
+                ..info
+            });
+        }
+
+        // Stripped binaries keep their DWARF in a separate debug info file,
+        // or in a `.dSYM` bundle next to them on macOS.
+        let mut debug_file = None;
+        {
+            let file = parse_object(bytes)?;
+            if !has_section(&file, ".debug_info") {
+                debug_file = match file.format() {
+                    object::BinaryFormat::MachO => macho::find_dsym(path.as_deref(), &file)?,
+                    _ => debuglink::find_debug_file(path.as_deref(), &file, &self.debug_dirs)?
+                        .map(|(found, contents)| {
+                            let range = 0..contents.len();
+                            (found, contents, range)
+                        }),
+                };
+            }
+        }
+        let (debug_path, data, range) = match debug_file {
+            Some((found, contents, range)) => (Some(found), Data::new(contents), range),
+            None => (path.clone(), data.clone(), range),
+        };
+        let file = parse_object(&data[range])?;
+
+        // Fully stripped binaries have no DWARF at all, but compilers and
+        // linkers leave their mark elsewhere too.
+        if self.toolchain_fallback && !has_section(&file, ".debug_info") {
+            return Ok(DebugInfo {
+                path,
+                arch,
+                member: None,
+                dwarf: Arc::new(load_dwarf(&data, &file)?),
+                dwp: None,
+                wasm_producers: None,
+                wasm_producers_error: None,
+                toolchain_records: toolchain::records(&file)?.into(),
+                type_units: self.type_units,
+                lenient: self.lenient,
+            });
+        }
+
+        for name in &[".debug_info", ".debug_abbrev"] {
+            if !has_section(&file, name) {
+                return Err(Error::MissingSection(name.to_string()));
+            }
+        }
+
+        // The remaining sections are optional. Producers stored inline with
+        // `DW_FORM_string` don't need any string section at all, so a missing
+        // string section is only reported for the units that refer to it.
+        let mut dwarf = load_dwarf(&data, &file)?;
+
+        // Debug info deduplicated by `dwz` refers to a supplementary object
+        // file for strings and partial units shared with other binaries.
+        let sup = debuglink::find_sup_file(debug_path.as_deref(), &file, &self.debug_dirs)?;
+        if let Some(contents) = sup {
+            let sup_data = Data::new(contents);
+            let sup_file = parse_object(&sup_data)?;
+            dwarf.set_sup(load_dwarf(&sup_data, &sup_file)?);
+        }
+
+        // Split DWARF that has been packaged up by `dwp` lives next to the
+        // file, as `<file>.dwp`.
+        let dwp_contents = match path {
+            Some(ref path) => split::read_dwp(path)?,
+            None => None,
+        };
+        let dwp = match dwp_contents {
+            Some(contents) => {
+                let dwp_data = Data::new(contents);
+                let dwp_file = parse_object(&dwp_data)?;
+                let empty = Reader::new(dwp_data.clone(), endian(&dwp_file)).range(0..0);
+                let dwp = DwarfPackage::load(
+                    |id| load_section(&dwp_data, &dwp_file, id, id.dwo_name()),
+                    empty,
+                )?;
+                Some(Arc::new(dwp))
+            }
+            None => None,
+        };
+
+        Ok(DebugInfo {
+            path,
+            arch,
+            member: None,
+            dwarf: Arc::new(dwarf),
+            dwp,
+            wasm_producers: None,
+            wasm_producers_error: None,
+            toolchain_records: Arc::new([]),
+            type_units: self.type_units,
+            lenient: self.lenient,
+        })
+    }
+
     /// Finish configuring and get an iterator over the `DW_AT_producer`s in the
     /// compilation units of the configured files.
     pub fn producers<F, T>(self, mut f: F) -> Result<T>
     where
         F: FnMut(&mut Producers) -> T,
     {
-        let mut contents = vec![];
-        {
-            let mut file = fs::File::open(self.file)?;
-            file.read_to_end(&mut contents)?;
+        Ok(f(&mut self.load()?.producers()))
+    }
+
+    /// Finish configuring and get an iterator over the compilation units of
+    /// the configured files.
+    pub fn compilation_units<F, T>(self, mut f: F) -> Result<T>
+    where
+        F: FnMut(&mut CompilationUnits) -> T,
+    {
+        Ok(f(&mut self.load()?.compilation_units()))
+    }
+}
+
+/// Load the debug info of the WebAssembly module at `range` within `data`,
+/// which was read from `path`.
+///
+/// Its DWARF sections are custom sections with the usual names. Release builds
+/// often have no DWARF, but still have a `producers` section, so the DWARF
+/// sections are only required when there is no `producers` section, and the
+/// toolchain fallback isn't enabled. Modules have no other toolchain records.
+fn load_wasm(
+    path: Option<path::PathBuf>,
+    data: &Data,
+    range: Range<usize>,
+    toolchain_fallback: bool,
+) -> Result<DebugInfo> {
+    let sections = wasm::custom_sections(&data[range])?;
+    let find = |name: &str| {
+        sections
+            .iter()
+            .find(|&&(section, _)| section == name)
+            .map(|&(_, contents)| contents)
+    };
+
+    // A malformed `producers` section is treated as if there were none, so
+    // that it doesn't keep the module's DWARF from being read.
+    let producers = find("producers").map(wasm::parse_producers);
+    let (wasm_producers, wasm_producers_error) = match producers {
+        Some(Ok(producers)) => (Some(Arc::new(producers)), None),
+        Some(Err(e)) => (None, Some(Arc::new(e))),
+        None => (None, None),
+    };
+    let dwarf_optional = wasm_producers.is_some() || toolchain_fallback;
+    if !dwarf_optional || find(".debug_info").is_some() {
+        for name in &[".debug_info", ".debug_abbrev"] {
+            if find(name).is_none() {
+                return Err(Error::MissingSection(name.to_string()));
+            }
+        }
+    }
+
+    // WebAssembly is always little-endian.
+    let dwarf = Dwarf::load(|id| -> Result<_> {
+        let contents = find(id.name()).unwrap_or(&[]);
+        Ok(reader::section(data, Cow::Borrowed(contents), RunTimeEndian::Little))
+    })?;
+
+    Ok(DebugInfo {
+        path,
+        arch: None,
+        member: None,
+        dwarf: Arc::new(dwarf),
+        dwp: None,
+        wasm_producers,
+        wasm_producers_error,
+        toolchain_records: Arc::new([]),
+        type_units: false,
+        lenient: false,
+    })
+}
+
+/// The range of the `size` bytes at `offset` within a file of `len` bytes, or
+/// an `Error::OutOfBounds` for `what` is there if they extend past its end.
+fn file_range(what: &'static str, offset: u64, size: u64, len: usize) -> Result<Range<usize>> {
+    match offset.checked_add(size) {
+        Some(end) if end <= len as u64 => Ok(offset as usize..end as usize),
+        _ => Err(Error::OutOfBounds { what, offset, size }),
+    }
+}
+
+/// Parse an object file, telling data in formats that we don't understand at
+/// all apart from object files that are corrupt.
+fn parse_object(data: &[u8]) -> Result<object::File<'_>> {
+    if object::FileKind::parse(data).is_err() {
+        return Err(Error::UnsupportedFormat("unrecognized file magic".into()));
+    }
+    Ok(object::File::parse(data)?)
+}
+
+/// The DWARF is encoded with the target's endianness, which need not match the
+/// host's when inspecting cross-compiled binaries.
+fn endian(file: &object::File) -> RunTimeEndian {
+    if file.is_little_endian() {
+        RunTimeEndian::Little
+    } else {
+        RunTimeEndian::Big
+    }
+}
+
+/// The DWARF sections that we read: those needed to parse units and their
+/// root entries, including the line program header and the `.debug_addr`
+/// entry of an indexed `DW_AT_low_pc` that `gimli` reads along with each unit,
+/// and the indexes of `.dwp` packages.
+const SECTIONS: &[SectionId] = &[
+    SectionId::DebugAbbrev,
+    SectionId::DebugAddr,
+    SectionId::DebugInfo,
+    SectionId::DebugTypes,
+    SectionId::DebugStr,
+    SectionId::DebugStrOffsets,
+    SectionId::DebugLineStr,
+    SectionId::DebugLine,
+    SectionId::DebugCuIndex,
+    SectionId::DebugTuIndex,
+];
+
+/// Load the DWARF sections that we read from the given file, whose data is
+/// `data`.
+fn load_dwarf(data: &Data, file: &object::File) -> Result<Dwarf<Reader>> {
+    Dwarf::load(|id| load_section(data, file, id, Some(id.name())))
+}
+
+/// Get a reader for the section `id`, which is named `name` in this kind of
+/// file.
+///
+/// The reader is empty if there is no such section, or no name for the kind
+/// of section being loaded, such as the `.dwo` variant of a section that split
+/// DWARF doesn't use. It is also empty for the sections that we never read,
+/// such as location and range lists, so that they aren't decompressed or
+/// relocated for nothing, and can't fail to be.
+fn load_section(
+    data: &Data,
+    file: &object::File,
+    id: SectionId,
+    name: Option<&str>,
+) -> Result<Reader> {
+    let section = match name {
+        Some(name) if SECTIONS.contains(&id) => get_section(file, name)?,
+        _ => None,
+    };
+    Ok(reader::section(
+        data,
+        section.unwrap_or(Cow::Borrowed(&[])),
+        endian(file),
+    ))
+}
+
+/// Get the data for the section with the given name, if the file has it.
+///
+/// Looking up `.debug_foo` also finds GNU-style compressed `.zdebug_foo`
+/// sections. Compressed sections are decompressed when the `compression`
+/// feature is enabled, and are an error otherwise. The sections of relocatable
+/// ELF objects have their relocations applied.
+fn get_section<'a>(file: &object::File<'a>, name: &str) -> Result<Option<Cow<'a, [u8]>>> {
+    if let Some(section) = file.section_by_name(name) {
+        let data = section.uncompressed_data()?;
+        return Ok(Some(reloc::relocate(file, &section, data)?));
+    }
+
+    // `object` only looks for `.zdebug_foo` in ELF and Mach-O files, but
+    // MinGW's `ld --compress-debug-sections` writes them into PE files too.
+    if let Some(zdebug) = zdebug_name(name) {
+        if let Some(section) = file.section_by_name(&zdebug) {
+            let data = decompress_zdebug(section.data()?)?;
+            return Ok(Some(reloc::relocate(file, &section, data)?));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Whether the file has the section with the given name, in the same way as
+/// `get_section`.
+fn has_section(file: &object::File, name: &str) -> bool {
+    file.section_by_name(name).is_some()
+        || zdebug_name(name).is_some_and(|zdebug| file.section_by_name(&zdebug).is_some())
+}
+
+/// The name of the GNU-style compressed variant of the given section, such as
+/// `.zdebug_info` for `.debug_info`.
+fn zdebug_name(name: &str) -> Option<String> {
+    name.strip_prefix(".debug_").map(|rest| format!(".zdebug_{}", rest))
+}
+
+/// Decompress the data of a `.zdebug_foo` section, which is `ZLIB`, then the
+/// big-endian uncompressed size, and then the zlib stream. Like GNU tools, we
+/// take data without the `ZLIB` header to be uncompressed.
+fn decompress_zdebug<'a>(data: &'a [u8]) -> Result<Cow<'a, [u8]>> {
+    if data.len() < 12 || !data.starts_with(b"ZLIB") {
+        return Ok(Cow::Borrowed(data));
+    }
+
+    let mut size = [0; 8];
+    size.copy_from_slice(&data[4..12]);
+    let compressed = object::CompressedData {
+        format: object::CompressionFormat::Zlib,
+        data: &data[12..],
+        uncompressed_size: u64::from_be_bytes(size),
+    };
+    Ok(compressed.decompress()?)
+}
+
+/// The outcome of loading one of the objects within a file, which is named by
+/// its archive member and architecture even if it couldn't be loaded.
+struct Loaded {
+    member: Option<String>,
+    arch: Option<String>,
+    result: Result<DebugInfo>,
+}
+
+/// The debug info of a shared library or executable, as loaded by
+/// `Options::load`.
+///
+/// This owns the data it was loaded from, so it can be kept around and
+/// iterated over any number of times. Cloning it is cheap, and shares that
+/// data.
+#[derive(Clone, Debug)]
+pub struct DebugInfo {
+    path: Option<path::PathBuf>,
+    arch: Option<String>,
+    member: Option<String>,
+    dwarf: Arc<Dwarf<Reader>>,
+    dwp: Option<Arc<DwarfPackage<Reader>>>,
+    wasm_producers: Option<Arc<WasmProducers>>,
+    wasm_producers_error: Option<Arc<Error>>,
+    toolchain_records: Arc<[ToolchainRecord]>,
+    type_units: bool,
+    lenient: bool,
+}
+
+impl DebugInfo {
+    /// The path of the file that the debug info was loaded for, or `None` if
+    /// it was loaded from memory. For the members of thin archives, this is
+    /// the member's own file.
+    pub fn path(&self) -> Option<&path::Path> {
+        self.path.as_deref()
+    }
+
+    /// The architecture of the universal binary slice that the debug info was
+    /// loaded for, such as `arm64`, or `None` if it wasn't a universal binary.
+    pub fn arch(&self) -> Option<&str> {
+        self.arch.as_deref()
+    }
+
+    /// The name of the archive member that the debug info was loaded for, such
+    /// as `foo.o`, or `None` if it wasn't loaded from an archive.
+    pub fn member(&self) -> Option<&str> {
+        self.member.as_deref()
+    }
+
+    /// The `producers` section of a WebAssembly module, which lists the source
+    /// languages, tools and SDKs that built it, or `None` if this isn't a
+    /// WebAssembly module or it has no `producers` section.
+    pub fn wasm_producers(&self) -> Option<&WasmProducers> {
+        self.wasm_producers.as_deref()
+    }
+
+    /// The error encountered parsing the `producers` section of a WebAssembly
+    /// module, if it is malformed. The module's DWARF is still read, and
+    /// `wasm_producers` is `None`, as if it had no `producers` section.
+    pub fn wasm_producers_error(&self) -> Option<&Error> {
+        self.wasm_producers_error.as_deref()
+    }
+
+    /// The toolchain records found outside of DWARF, in order, if the file has
+    /// no DWARF and `Options::toolchain_fallback` is enabled. Otherwise, this
+    /// is empty.
+    pub fn toolchain_records(&self) -> &[ToolchainRecord] {
+        &self.toolchain_records
+    }
+
+    /// Get an iterator over the compilation units, starting from the first,
+    /// and then over the `.debug_types` section's type units if
+    /// `Options::type_units` is enabled.
+    pub fn compilation_units(&self) -> CompilationUnits {
+        CompilationUnits {
+            info: self.clone(),
+            section: Some(SectionId::DebugInfo),
+            offset: 0,
+            diagnostics: vec![],
         }
+    }
 
-        let file = object::File::parse(&contents[..])?;
+    /// Get an iterator over the `DW_AT_producer`s of the compilation units,
+    /// starting from the first.
+    pub fn producers(&self) -> Producers {
+        Producers {
+            units: self.compilation_units(),
+        }
+    }
+}
 
-        let debug_info = file.get_section(".debug_info")
-            .ok_or_else(|| Error::from("missing .debug_info section"))?;
-        let debug_info = DebugInfo::new(debug_info, gimli::NativeEndian);
+/// A `FallibleIterator` yielding a `CompilationUnit` for each compilation unit
+/// in the configured file.
+///
+/// Partial units are not yielded themselves, but the attributes of the partial
+/// units that a compilation unit imports fill in any that it lacks, as do the
+/// attributes of a skeleton unit's split unit. Type units are only yielded if
+/// `Options::type_units` is enabled.
+///
+/// If a compilation unit can't be read, for example because its attributes
+/// refer to a string section that the file doesn't have, then an error is
+/// returned for that unit, and calling `next` again resumes with the following
+/// unit. If a unit's header can't be parsed, the rest of its section can't be
+/// either, and the error for it is the last one from that section.
+///
+/// With `Options::lenient` enabled, errors are recorded as `Diagnostic`s for
+/// the units they were encountered in, and iteration carries on without
+/// returning them. A unit whose header can't be parsed is skipped over using
+/// just its length, so that the units after it can still be read.
+#[derive(Debug)]
+pub struct CompilationUnits {
+    info: DebugInfo,
+    /// The section that the next unit header is in, if there are any left.
+    section: Option<SectionId>,
+    /// The offset of the next unit header within `section`.
+    offset: usize,
+    diagnostics: Vec<Diagnostic>,
+}
 
-        let debug_abbrev = file.get_section(".debug_abbrev")
-            .ok_or_else(|| Error::from("missing .debug_abbrev section"))?;
-        let debug_abbrev = DebugAbbrev::new(debug_abbrev, gimli::NativeEndian);
+impl CompilationUnits {
+    /// Get the next `CompilationUnit`, if any.
+    ///
+    /// It is usually more ergonomic to use `FallibleIterator` combinators, but
+    /// this method exists as an escape hatch.
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Result<Option<CompilationUnit>> {
+        while let Some((offset, header)) = self.next_header() {
+            match header.and_then(|header| self.read_unit(header)) {
+                Ok(Some(unit)) => return Ok(Some(unit)),
+                Ok(None) => {}
+                Err(e) if self.info.lenient => self.diagnostics.push(unit::diagnostic(offset, e)),
+                Err(e) => return Err(unit::unit_error(self.info.path(), offset, e)),
+            }
+        }
+        Ok(None)
+    }
 
-        let debug_str = file.get_section(".debug_str")
-            .ok_or_else(|| Error::from("missing .debug_str section"))?;
-        let debug_str = DebugStr::new(debug_str, gimli::NativeEndian);
+    /// The units that couldn't be read so far, in order, if
+    /// `Options::lenient` is enabled. Otherwise, this is empty.
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
 
-        let headers = debug_info.units();
+    /// Read all of the remaining compilation units, or the errors encountered
+    /// reading them, in order, along with the diagnostics for the units that
+    /// couldn't be read if lenient.
+    ///
+    /// With the `rayon` feature, the units are read in parallel if `parallel`
+    /// is set.
+    #[cfg_attr(not(feature = "rayon"), allow(unused_variables))]
+    fn collect_results(
+        mut self,
+        parallel: bool,
+    ) -> (Vec<Result<CompilationUnit>>, Vec<Diagnostic>) {
+        // Splitting the sections into unit headers is cheap, and must be done
+        // sequentially.
+        let mut headers = vec![];
+        while let Some(header) = self.next_header() {
+            headers.push(header);
+        }
 
-        let mut producers = Producers {
-            debug_str,
-            debug_abbrev,
-            headers,
+        let read = |(offset, header): (UnitSectionOffset, Result<UnitHeader<Reader>>)| {
+            (offset, header.and_then(|header| self.read_unit(header)))
         };
+        #[cfg(feature = "rayon")]
+        {
+            use rayon::prelude::*;
+            if parallel {
+                // `collect` keeps the results in the same order as the headers.
+                let read = headers.into_par_iter().map(read).collect();
+                return self.sort_results(read);
+            }
+        }
+
+        let read = headers.into_iter().map(read).collect();
+        self.sort_results(read)
+    }
 
-        Ok(f(&mut producers))
+    /// Sort the results of reading each unit, in order, into the units or
+    /// the errors reading them, and the diagnostics for the units that
+    /// couldn't be read if lenient.
+    fn sort_results(
+        mut self,
+        read: Vec<(UnitSectionOffset, Result<Option<CompilationUnit>>)>,
+    ) -> (Vec<Result<CompilationUnit>>, Vec<Diagnostic>) {
+        let mut results = vec![];
+        let mut diagnostics = mem::take(&mut self.diagnostics);
+        for (offset, result) in read {
+            match result {
+                Ok(Some(unit)) => results.push(Ok(unit)),
+                Ok(None) => {}
+                Err(e) if self.info.lenient => diagnostics.push(unit::diagnostic(offset, e)),
+                Err(e) => results.push(Err(unit::unit_error(self.info.path(), offset, e))),
+            }
+        }
+        (results, diagnostics)
+    }
+
+    /// Get the offset of the next unit, and its header or the error parsing
+    /// it, from `.debug_info`, and then from `.debug_types` if type units are
+    /// included.
+    ///
+    /// After an error parsing a header, the next one is found from the unit's
+    /// length if lenient, and otherwise there are no more in its section.
+    fn next_header(&mut self) -> Option<(UnitSectionOffset, Result<UnitHeader<Reader>>)> {
+        loop {
+            let section = self.section?;
+            let dwarf = &self.info.dwarf;
+            let data = match section {
+                SectionId::DebugTypes => dwarf.debug_types.reader(),
+                _ => dwarf.debug_info.reader(),
+            };
+            if self.offset >= data.len() {
+                self.section = match section {
+                    SectionId::DebugInfo if self.info.type_units => Some(SectionId::DebugTypes),
+                    _ => None,
+                };
+                self.offset = 0;
+                continue;
+            }
+
+            let offset = self.offset;
+            let (unit_offset, header) = match section {
+                SectionId::DebugTypes => (
+                    DebugTypesOffset(offset).into(),
+                    type_unit_header(data, offset),
+                ),
+                _ => (
+                    DebugInfoOffset(offset).into(),
+                    dwarf.debug_info.header_from_offset(DebugInfoOffset(offset)),
+                ),
+            };
+            self.offset = match header {
+                Ok(ref header) => offset + header.length_including_self(),
+                Err(_) if self.info.lenient => unit_end(data, offset).unwrap_or(data.len()),
+                Err(_) => data.len(),
+            };
+            return Some((unit_offset, header.map_err(Error::from)));
+        }
+    }
+
+    /// Read the unit with the given header, or `None` if it isn't a
+    /// compilation unit, or a type unit that we aren't including.
+    fn read_unit(&self, unit_header: UnitHeader<Reader>) -> Result<Option<CompilationUnit>> {
+        if unit::kind(unit_header.type_()) == UnitKind::Type && !self.info.type_units {
+            return Ok(None);
+        }
+
+        // Constructing the `Unit` resolves attributes like
+        // `DW_AT_str_offsets_base` that string forms depend upon.
+        let unit = self.info.dwarf.unit(unit_header)?;
+
+        // Partial units hold entries that `dwz` factored out of several
+        // compilation units, and are accounted for by following the
+        // compilation units' imports instead.
+        if root_tag(&unit)? == gimli::DW_TAG_partial_unit {
+            return Ok(None);
+        }
+
+        let mut attrs = RootAttrs::read(&self.info.dwarf, &unit)?;
+
+        if attrs.producer.is_none() {
+            if let Some(imported) = dwz::imported_attrs(&self.info.dwarf, &unit)? {
+                attrs.fill_from(imported);
+            }
+        }
+
+        // Skeleton units from `-gsplit-dwarf` leave the producer to the
+        // split unit in the corresponding `.dwo` file or `.dwp` package.
+        if attrs.producer.is_none() {
+            let info = &self.info;
+            let split = split::split_attrs(info.path.as_deref(), &info.dwarf, info.dwp.as_deref(), &unit)?;
+            if let Some(split) = split {
+                attrs.fill_from(split);
+            }
+        }
+
+        Ok(Some(unit::compilation_unit(&unit, attrs)))
+    }
+}
+
+/// Parse the header of the type unit at `offset` within the `.debug_types`
+/// section `data`.
+fn type_unit_header(data: &Reader, offset: usize) -> gimli::Result<UnitHeader<Reader>> {
+    // Unlike with `.debug_info`, gimli only parses these headers in order, so
+    // parse it as the first of the rest of the section, and then put back the
+    // offset from the start of the section.
+    let mut input = data.clone();
+    input.skip(offset)?;
+    let header = match DebugTypes::from(input).units().next()? {
+        Some(header) => header,
+        None => return Err(gimli::Error::MissingUnitDie),
+    };
+    let entries = offset + header.header_size()..offset + header.length_including_self();
+    Ok(UnitHeader::new(
+        header.encoding(),
+        header.unit_length(),
+        header.type_(),
+        header.debug_abbrev_offset(),
+        DebugTypesOffset(offset).into(),
+        data.range(entries),
+    ))
+}
+
+/// Get the offset just past the end of the unit at `offset` within `data`,
+/// from its length alone.
+fn unit_end(data: &Reader, offset: usize) -> Option<usize> {
+    let mut input = data.clone();
+    input.skip(offset).ok()?;
+    let (length, format) = input.read_initial_length().ok()?;
+    offset
+        .checked_add(usize::from(format.initial_length_size()))?
+        .checked_add(length)
+}
+
+/// Get the tag of the given unit's root entry.
+fn root_tag(unit: &Unit<Reader>) -> Result<gimli::DwTag> {
+    let mut entries = unit.entries();
+    match entries.next_dfs()? {
+        Some((_, entry)) => Ok(entry.tag()),
+        None => Err(gimli::Error::MissingUnitDie.into()),
+    }
+}
+
+impl FallibleIterator for CompilationUnits {
+    type Error = Error;
+    type Item = CompilationUnit;
+
+    fn next(&mut self) -> Result<Option<Self::Item>> {
+        CompilationUnits::next(self)
     }
 }
 
 /// A `FallibleIterator` yielding `String` values for the `DW_AT_producer` for
 /// each compilation unit in the configured file.
+///
+/// This is a convenience on top of `CompilationUnits` that skips units without
+/// a `DW_AT_producer`. Errors are reported and resumed from in the same way.
 #[derive(Debug)]
-pub struct Producers<'a> {
-    debug_str: DebugStr<EndianBuf<'a, NativeEndian>>,
-    debug_abbrev: DebugAbbrev<EndianBuf<'a, NativeEndian>>,
-    headers: CompilationUnitHeadersIter<EndianBuf<'a, NativeEndian>>,
+pub struct Producers {
+    units: CompilationUnits,
 }
 
-impl<'a> Producers<'a> {
+impl Producers {
     /// Get the next `DW_AT_producer`, if any.
     ///
     /// It is usually more ergonomic to use `FallibleIterator` combinators, but
     /// this method exists as an escape hatch.
+    #[allow(clippy::should_implement_trait)]
     pub fn next(&mut self) -> Result<Option<String>> {
-        loop {
-            let unit_header = match self.headers.next()? {
-                None => return Ok(None),
-                Some(h) => h,
-            };
-
-            let abbrevs = unit_header.abbreviations(&self.debug_abbrev)?;
-            let mut tree = unit_header.entries_tree(&abbrevs, None)?;
-            let root = tree.root()?;
-            let mut attrs = root.entry().attrs();
-
-            while let Some(attr) = attrs.next()? {
-                if let gimli::DW_AT_producer = attr.name() {
-                    match attr.value() {
-                        gimli::AttributeValue::DebugStrRef(offset) => {
-                            return Ok(Some(self.debug_str.get_str(offset)?.to_string()?.into()));
-                        }
-                        gimli::AttributeValue::Block(data) => {
-                            return Ok(Some(data.to_string()?.into()));
-                        }
-                        // Unknown kind of `DW_AT_producer` value; skip it.
-                        _ => continue,
-                    }
-                }
+        while let Some(unit) = self.units.next()? {
+            if let Some(producer) = unit.producer() {
+                return Ok(Some(producer.raw().to_string()));
             }
 
             // No DW_AT_producer for this compilation unit, so just continue to
-            // the next unit header.
+            // the next one.
         }
+        Ok(None)
+    }
+
+    /// The units that couldn't be read so far, if `Options::lenient` is
+    /// enabled. See `CompilationUnits::diagnostics`.
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        self.units.diagnostics()
     }
 }
 
-impl<'a> FallibleIterator for Producers<'a> {
+impl FallibleIterator for Producers {
     type Error = Error;
     type Item = String;
 
@@ -0,0 +1,148 @@
+//! Mach-O specifics: the architecture slices of universal ("fat") binaries,
+//! and the `.dSYM` bundles that hold the debug info of stripped binaries.
+
+use super::{file_range, Error, Result};
+use contents::{self, Contents};
+use object::macho;
+use object::read::macho::{FatArch, MachOFatFile32, MachOFatFile64};
+use object::{self, FileKind, Object};
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+/// One architecture's slice of a universal binary.
+#[derive(Debug)]
+pub struct Arch {
+    /// The architecture's name, as used by `lipo`, such as `arm64`.
+    pub name: String,
+    /// Where the slice is within the universal binary.
+    pub range: Range<usize>,
+}
+
+/// Get the architecture slices of `data`, or `None` if it isn't a universal
+/// binary.
+pub fn fat_arches(data: &[u8]) -> Result<Option<Vec<Arch>>> {
+    match FileKind::parse(data) {
+        Ok(FileKind::MachOFat32) => arches(data, MachOFatFile32::parse(data)?.arches()).map(Some),
+        Ok(FileKind::MachOFat64) => arches(data, MachOFatFile64::parse(data)?.arches()).map(Some),
+        _ => Ok(None),
+    }
+}
+
+fn arches<A: FatArch>(data: &[u8], arches: &[A]) -> Result<Vec<Arch>> {
+    arches
+        .iter()
+        .map(|arch| {
+            let (offset, size) = arch.file_range();
+            Ok(Arch {
+                name: arch_name(arch.cputype(), arch.cpusubtype()),
+                range: file_range("fat arch", offset, size, data.len())?,
+            })
+        })
+        .collect()
+}
+
+/// The name that `lipo` and friends use for the given CPU type and subtype.
+fn arch_name(cputype: u32, cpusubtype: u32) -> String {
+    let name = match (cputype, cpusubtype & !macho::CPU_SUBTYPE_MASK) {
+        (macho::CPU_TYPE_X86, _) => "i386",
+        (macho::CPU_TYPE_X86_64, macho::CPU_SUBTYPE_X86_64_H) => "x86_64h",
+        (macho::CPU_TYPE_X86_64, _) => "x86_64",
+        (macho::CPU_TYPE_ARM, macho::CPU_SUBTYPE_ARM_V7) => "armv7",
+        (macho::CPU_TYPE_ARM, macho::CPU_SUBTYPE_ARM_V7S) => "armv7s",
+        (macho::CPU_TYPE_ARM, macho::CPU_SUBTYPE_ARM_V7K) => "armv7k",
+        (macho::CPU_TYPE_ARM, _) => "arm",
+        (macho::CPU_TYPE_ARM64, macho::CPU_SUBTYPE_ARM64E) => "arm64e",
+        (macho::CPU_TYPE_ARM64, _) => "arm64",
+        (macho::CPU_TYPE_ARM64_32, _) => "arm64_32",
+        (macho::CPU_TYPE_POWERPC, _) => "ppc",
+        (macho::CPU_TYPE_POWERPC64, _) => "ppc64",
+        _ => return format!("cputype {:#x}", cputype),
+    };
+    name.into()
+}
+
+/// Whether `path` is a `.dSYM` bundle directory.
+pub fn is_dsym_bundle(path: &Path) -> bool {
+    path.extension() == Some("dSYM".as_ref()) && path.is_dir()
+}
+
+/// Find the DWARF file within a `.dSYM` bundle: the one in
+/// `Contents/Resources/DWARF` named after the bundle, such as `app` for
+/// `app.dSYM`, or otherwise the only file there.
+pub fn bundle_dwarf_file(bundle: &Path) -> Result<PathBuf> {
+    let dir = bundle.join("Contents/Resources/DWARF");
+    if let Some(name) = bundle.file_stem() {
+        let named = dir.join(name);
+        if named.is_file() {
+            return Ok(named);
+        }
+    }
+
+    let mut files = vec![];
+    for entry in fs::read_dir(&dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_file() {
+            files.push(entry.path());
+        }
+    }
+    if files.len() == 1 {
+        return Ok(files.pop().unwrap());
+    }
+
+    Err(Error::MissingDsymFile(dir))
+}
+
+/// Find and read the `.dSYM` bundle next to `path` for `file`, which was read
+/// from it. Returns the DWARF file's path and contents, and the range of the
+/// slice matching `file` within it.
+///
+/// A slice matches if it has the same UUID as `file`, or if either of them
+/// has no UUID, the same architecture.
+pub fn find_dsym(
+    path: Option<&Path>,
+    file: &object::File,
+) -> Result<Option<(PathBuf, Contents, Range<usize>)>> {
+    let path = match path {
+        Some(path) => path,
+        None => return Ok(None),
+    };
+    let mut bundle = path.as_os_str().to_owned();
+    bundle.push(".dSYM");
+    let bundle = PathBuf::from(bundle);
+    if !bundle.is_dir() {
+        return Ok(None);
+    }
+
+    let dwarf_path = bundle_dwarf_file(&bundle)?;
+    let data = match contents::load_if_exists(&dwarf_path)? {
+        Some(data) => data,
+        None => return Ok(None),
+    };
+
+    let ranges = match fat_arches(&data)? {
+        Some(arches) => arches.into_iter().map(|arch| arch.range).collect(),
+        None => vec![Range {
+            start: 0,
+            end: data.len(),
+        }],
+    };
+    for range in ranges {
+        let candidate = match data.get(range.clone()).map(object::File::parse) {
+            Some(Ok(candidate)) => candidate,
+            _ => continue,
+        };
+        if matches(file, &candidate)? {
+            return Ok(Some((dwarf_path, data, range)));
+        }
+    }
+
+    Ok(None)
+}
+
+fn matches(file: &object::File, candidate: &object::File) -> Result<bool> {
+    match (file.mach_uuid()?, candidate.mach_uuid()?) {
+        (Some(a), Some(b)) => Ok(a == b),
+        _ => Ok(file.architecture() == candidate.architecture()),
+    }
+}
@@ -0,0 +1,358 @@
+//! Parsing `DW_AT_producer` strings into their compiler, version, and flags.
+
+use std::fmt;
+
+/// A `DW_AT_producer` value, parsed into the compiler that produced the
+/// compilation unit, its version, and the flags it was invoked with.
+///
+/// Parsing never fails: unrecognized producers have an `Unknown` compiler and
+/// whatever could be salvaged from them. The original string is always
+/// available via `raw`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Producer {
+    raw: String,
+    compiler: Compiler,
+    version: Option<Version>,
+    rustc: Option<RustcInfo>,
+    flags: Vec<String>,
+}
+
+/// The compiler family that produced a compilation unit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Compiler {
+    /// GCC, for any of its front ends: C, C++, Fortran, Ada, Go, D, etc.
+    Gcc,
+    /// The GNU assembler.
+    GnuAssembler,
+    /// Upstream or distribution builds of clang.
+    Clang,
+    /// Apple's clang, whose version numbers don't match upstream's.
+    AppleClang,
+    /// LLVM's Fortran compiler.
+    Flang,
+    /// The Rust compiler.
+    Rustc,
+    /// The Go toolchain's `cmd/compile`.
+    Go,
+    /// The Swift compiler.
+    Swift,
+    /// Intel's C and C++ compilers, both classic `icc` and oneAPI `icx`.
+    Intel,
+    /// Intel's Fortran compilers, both classic `ifort` and oneAPI `ifx`.
+    IntelFortran,
+    /// The LLVM-based D compiler.
+    Ldc,
+    /// The Digital Mars D compiler.
+    Dmd,
+    /// The Zig compiler.
+    Zig,
+    /// A producer we don't recognize.
+    Unknown,
+}
+
+/// A compiler version, such as `12.2.0` or `1.75.0-nightly`.
+///
+/// Missing minor and patch components are zero, and components beyond the
+/// patch, such as Intel's build numbers, are dropped.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Version {
+    /// The major version.
+    pub major: u64,
+    /// The minor version.
+    pub minor: u64,
+    /// The patch version.
+    pub patch: u64,
+    /// Whatever followed the numeric components, such as `nightly`, `rc2`, or
+    /// a distribution's package revision.
+    pub pre: Option<String>,
+}
+
+/// Details of the `rustc` build that produced a compilation unit.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RustcInfo {
+    /// The release channel.
+    pub channel: Channel,
+    /// The abbreviated commit hash that `rustc` was built from, if known.
+    pub commit_hash: Option<String>,
+    /// The date of that commit, as `YYYY-MM-DD`, if known.
+    pub commit_date: Option<String>,
+}
+
+/// A Rust release channel.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Channel {
+    /// A stable release.
+    Stable,
+    /// A beta release.
+    Beta,
+    /// A nightly release.
+    Nightly,
+    /// A local development build.
+    Dev,
+}
+
+impl Producer {
+    /// Parse the given `DW_AT_producer` string.
+    pub fn parse<S: Into<String>>(raw: S) -> Producer {
+        let raw = raw.into();
+        let mut producer = Producer {
+            compiler: Compiler::Unknown,
+            version: None,
+            rustc: None,
+            flags: vec![],
+            raw: String::new(),
+        };
+        producer.parse_raw(&raw);
+        producer.raw = raw;
+        producer
+    }
+
+    /// The original, unparsed `DW_AT_producer` string.
+    pub fn raw(&self) -> &str {
+        &self.raw
+    }
+
+    /// The compiler family that produced the compilation unit.
+    pub fn compiler(&self) -> Compiler {
+        self.compiler
+    }
+
+    /// The compiler's version, if it could be determined.
+    pub fn version(&self) -> Option<&Version> {
+        self.version.as_ref()
+    }
+
+    /// Details of the `rustc` build, if the compiler is `rustc`.
+    pub fn rustc(&self) -> Option<&RustcInfo> {
+        self.rustc.as_ref()
+    }
+
+    /// The command line flags recorded in the producer, such as GCC's
+    /// `-grecord-gcc-switches` output, one token per flag.
+    pub fn flags(&self) -> &[String] {
+        &self.flags
+    }
+
+    fn parse_raw(&mut self, raw: &str) {
+        if let Some(rest) = after(raw, "rustc version ") {
+            self.compiler = Compiler::Rustc;
+            self.parse_rustc(rest);
+            return;
+        }
+
+        if let Some(rest) = after(raw, "Go cmd/compile ") {
+            // Go appends any enabled experiments after a semicolon, e.g.
+            // "Go cmd/compile go1.20.1; regabi".
+            let mut parts = rest.splitn(2, ';');
+            self.compiler = Compiler::Go;
+            self.version = parts.next().and_then(Version::parse);
+            self.flags = parts
+                .next()
+                .map(|experiments| {
+                    experiments
+                        .split(|c: char| c == ',' || c.is_whitespace())
+                        .filter(|s| !s.is_empty())
+                        .map(String::from)
+                        .collect()
+                })
+                .unwrap_or_default();
+            return;
+        }
+
+        let (compiler, rest) = if let Some(rest) = after(raw, "GNU AS ") {
+            (Compiler::GnuAssembler, rest)
+        } else if let Some(rest) = raw.strip_prefix("GNU ") {
+            // Skip the language, e.g. "GNU C17 12.2.0" or "GNU Fortran2008 12.2.0".
+            (Compiler::Gcc, skip_to_version(rest))
+        } else if let Some(rest) = after(raw, "Apple clang version ") {
+            (Compiler::AppleClang, rest)
+        } else if let Some(rest) = after(raw, "flang version ")
+            .or_else(|| after(raw, "flang-new version "))
+        {
+            (Compiler::Flang, rest)
+        } else if let Some(rest) = after(raw, "clang version ") {
+            (Compiler::Clang, rest)
+        } else if let Some(rest) = after(raw, "Swift version ") {
+            (Compiler::Swift, rest)
+        } else if raw.starts_with("Intel(R) Fortran") {
+            (Compiler::IntelFortran, intel_version(raw))
+        } else if raw.starts_with("Intel(R)") {
+            (Compiler::Intel, intel_version(raw))
+        } else if let Some(rest) = after(raw, "LDC ") {
+            (Compiler::Ldc, rest)
+        } else if let Some(rest) = after(raw, "Digital Mars D ") {
+            (Compiler::Dmd, rest)
+        } else if let Some(rest) = after(raw, "zig ") {
+            (Compiler::Zig, rest)
+        } else {
+            self.flags = flags(raw);
+            return;
+        };
+
+        self.compiler = compiler;
+        self.version = rest.split_whitespace().next().and_then(Version::parse);
+        self.flags = flags(rest);
+    }
+
+    /// Parse the remainder of a "rustc version 1.22.0-nightly (088216fb9
+    /// 2017-09-04)" producer, after the "rustc version ".
+    fn parse_rustc(&mut self, rest: &str) {
+        let mut tokens = rest.split_whitespace();
+        self.version = tokens.next().and_then(Version::parse);
+
+        let channel = match self.version.as_ref().and_then(|v| v.pre.as_ref()) {
+            None => Channel::Stable,
+            Some(pre) if pre.starts_with("beta") => Channel::Beta,
+            Some(pre) if pre.starts_with("nightly") => Channel::Nightly,
+            Some(_) => Channel::Dev,
+        };
+
+        let mut commit_hash = None;
+        let mut commit_date = None;
+        if let (Some(hash), Some(date)) = (tokens.next(), tokens.next()) {
+            if hash.starts_with('(') {
+                let hash = hash.trim_start_matches('(');
+                let date = date.trim_end_matches(')');
+                if hash.chars().all(|c| c.is_ascii_hexdigit()) && is_date(date) {
+                    commit_hash = Some(hash.to_string());
+                    commit_date = Some(date.to_string());
+                }
+            }
+        }
+
+        self.rustc = Some(RustcInfo {
+            channel,
+            commit_hash,
+            commit_date,
+        });
+    }
+}
+
+impl fmt::Display for Producer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.raw)
+    }
+}
+
+impl fmt::Display for Compiler {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            Compiler::Gcc => "GCC",
+            Compiler::GnuAssembler => "GNU AS",
+            Compiler::Clang => "clang",
+            Compiler::AppleClang => "Apple clang",
+            Compiler::Flang => "flang",
+            Compiler::Rustc => "rustc",
+            Compiler::Go => "Go",
+            Compiler::Swift => "Swift",
+            Compiler::Intel => "Intel C/C++",
+            Compiler::IntelFortran => "Intel Fortran",
+            Compiler::Ldc => "LDC",
+            Compiler::Dmd => "DMD",
+            Compiler::Zig => "Zig",
+            Compiler::Unknown => "unknown",
+        })
+    }
+}
+
+impl Version {
+    /// Parse a version such as `12.2.0`, `v2.105.0`, `go1.21rc2` or
+    /// `14.0.0-1ubuntu1.1`.
+    pub fn parse(s: &str) -> Option<Version> {
+        let s = s.trim_matches(|c: char| !c.is_ascii_alphanumeric());
+        let s = s.trim_start_matches("go").trim_start_matches('v');
+
+        let (numbers, mut pre) = match s.find('-') {
+            Some(i) => (&s[..i], Some(s[i + 1..].to_string())),
+            None => (s, None),
+        };
+
+        let mut components = [0; 3];
+        for (i, component) in numbers.split('.').enumerate() {
+            let digits = component
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(component.len());
+            if digits == 0 {
+                return None;
+            }
+            if i < components.len() {
+                components[i] = component[..digits].parse().ok()?;
+            }
+            if digits < component.len() {
+                // Something like the "rc2" in "1.21rc2".
+                if pre.is_none() {
+                    pre = Some(component[digits..].to_string());
+                }
+                break;
+            }
+        }
+
+        Some(Version {
+            major: components[0],
+            minor: components[1],
+            patch: components[2],
+            pre: pre.filter(|pre| !pre.is_empty()),
+        })
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)?;
+        if let Some(ref pre) = self.pre {
+            write!(f, "-{}", pre)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Channel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            Channel::Stable => "stable",
+            Channel::Beta => "beta",
+            Channel::Nightly => "nightly",
+            Channel::Dev => "dev",
+        })
+    }
+}
+
+/// The rest of `s` after the first occurrence of `pattern`.
+fn after<'a>(s: &'a str, pattern: &str) -> Option<&'a str> {
+    s.find(pattern).map(|i| &s[i + pattern.len()..])
+}
+
+/// Skip ahead to the first token that starts with a digit.
+fn skip_to_version(s: &str) -> &str {
+    let mut rest = s;
+    while let Some(token) = rest.split_whitespace().next() {
+        if token.starts_with(|c: char| c.is_ascii_digit()) {
+            break;
+        }
+        rest = rest.trim_start()[token.len()..].trim_start();
+    }
+    rest
+}
+
+/// Intel's classic compilers say "..., Version 19.1.3.304 Build 20200925",
+/// while the oneAPI ones say "Intel(R) oneAPI DPC++/C++ Compiler 2023.2.0".
+fn intel_version(raw: &str) -> &str {
+    after(raw, "Version ").unwrap_or_else(|| skip_to_version(raw))
+}
+
+/// The whitespace-separated tokens starting at the first one that looks like a
+/// command line flag.
+fn flags(s: &str) -> Vec<String> {
+    s.split_whitespace()
+        .skip_while(|token| !token.starts_with('-'))
+        .map(String::from)
+        .collect()
+}
+
+fn is_date(s: &str) -> bool {
+    s.len() == 10
+        && s.char_indices().all(|(i, c)| match i {
+            4 | 7 => c == '-',
+            _ => c.is_ascii_digit(),
+        })
+}
@@ -0,0 +1,55 @@
+//! The DWARF reader type, which owns a reference to the file data that it
+//! reads from, so that loaded debug info can outlive the function that loaded
+//! it.
+
+use contents::Contents;
+use gimli::{self, EndianReader, RunTimeEndian};
+use std::borrow::Cow;
+use std::ops::Deref;
+use std::sync::Arc;
+
+/// An input file's data, shared between the readers for all of its sections.
+#[derive(Clone, Debug)]
+pub struct Data(Arc<Contents>);
+
+impl Data {
+    /// Share the given file contents.
+    pub fn new(contents: Contents) -> Data {
+        Data(Arc::new(contents))
+    }
+}
+
+impl Deref for Data {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+// Safety: the bytes live behind the `Arc`, in either a memory mapping or a
+// `Vec` that is never modified, so they stay put when a `Data` is moved or
+// cloned.
+unsafe impl gimli::StableDeref for Data {}
+unsafe impl gimli::CloneStableDeref for Data {}
+
+/// The DWARF reader type, using the object file's endianness.
+pub type Reader = EndianReader<RunTimeEndian, Data>;
+
+/// Make a reader for a section of the file with the given data.
+///
+/// Sections are usually borrowed straight out of the file's data, in which case
+/// the reader shares it. Decompressed sections get their own data.
+pub fn section(data: &Data, section: Cow<[u8]>, endian: RunTimeEndian) -> Reader {
+    let bytes = match section {
+        Cow::Borrowed(bytes) => bytes,
+        Cow::Owned(bytes) => return Reader::new(Data::new(Contents::Read(bytes)), endian),
+    };
+
+    let start = (bytes.as_ptr() as usize).wrapping_sub(data.as_ptr() as usize);
+    match start.checked_add(bytes.len()) {
+        Some(end) if end <= data.len() => Reader::new(data.clone(), endian).range(start..end),
+        // Missing sections are empty slices that don't point into the file.
+        _ => Reader::new(Data::new(Contents::Read(bytes.to_vec())), endian),
+    }
+}
@@ -0,0 +1,84 @@
+//! Applying the relocations of debug sections in relocatable ELF objects.
+//!
+//! In unlinked `.o` files, references from one debug section into another,
+//! such as a `DW_FORM_strp` offset into `.debug_str` or a unit's offset into
+//! `.debug_abbrev`, are often left as zero, with a relocation telling the
+//! linker what to fill in. Linked files have no such relocations left.
+
+use super::Result;
+use object::{
+    self, BinaryFormat, Object, ObjectKind, ObjectSection, ObjectSymbol, RelocationKind,
+    RelocationTarget,
+};
+use std::borrow::Cow;
+use std::convert::TryFrom;
+
+/// Apply the relocations of `section` to its data, `data`, if `file` is a
+/// relocatable ELF object.
+///
+/// Only absolute relocations are applied, which are what references between
+/// debug sections use. Others, such as RISC-V's pairs of additions and
+/// subtractions for the differences between labels in `DW_AT_high_pc`, only
+/// affect attributes we don't read, and are left as they are.
+pub fn relocate<'a>(
+    file: &object::File<'a>,
+    section: &object::Section<'a, '_>,
+    data: Cow<'a, [u8]>,
+) -> Result<Cow<'a, [u8]>> {
+    if file.format() != BinaryFormat::Elf || file.kind() != ObjectKind::Relocatable {
+        return Ok(data);
+    }
+    let mut relocations = section.relocations().peekable();
+    if relocations.peek().is_none() {
+        return Ok(data);
+    }
+
+    let mut data = data.into_owned();
+    for (offset, relocation) in relocations {
+        let size = match (relocation.kind(), relocation.size()) {
+            (RelocationKind::Absolute, size @ (8 | 16 | 32 | 64)) => size as usize / 8,
+            _ => continue,
+        };
+        let target = match relocation.target() {
+            RelocationTarget::Symbol(index) => file.symbol_by_index(index)?.address(),
+            // Section symbols, which most debug section relocations are
+            // against, are at the start of their section.
+            RelocationTarget::Section(_) | RelocationTarget::Absolute => 0,
+            _ => continue,
+        };
+        let target = target.wrapping_add(relocation.addend() as u64);
+
+        let place = usize::try_from(offset)
+            .ok()
+            .and_then(|start| Some(start..start.checked_add(size)?))
+            .and_then(|range| data.get_mut(range))
+            .ok_or("invalid relocation offset")?;
+        let value = if relocation.has_implicit_addend() {
+            read(place, file.is_little_endian()).wrapping_add(target)
+        } else {
+            target
+        };
+        write(place, value, file.is_little_endian());
+    }
+    Ok(Cow::Owned(data))
+}
+
+fn read(place: &[u8], little_endian: bool) -> u64 {
+    let mut bytes = [0; 8];
+    if little_endian {
+        bytes[..place.len()].copy_from_slice(place);
+        u64::from_le_bytes(bytes)
+    } else {
+        bytes[8 - place.len()..].copy_from_slice(place);
+        u64::from_be_bytes(bytes)
+    }
+}
+
+fn write(place: &mut [u8], value: u64, little_endian: bool) {
+    let len = place.len();
+    if little_endian {
+        place.copy_from_slice(&value.to_le_bytes()[..len]);
+    } else {
+        place.copy_from_slice(&value.to_be_bytes()[8 - len..]);
+    }
+}
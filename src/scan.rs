@@ -0,0 +1,435 @@
+//! Scanning many files and directory trees at once.
+
+use super::{CompilationUnit, Diagnostic, Error, Options, Result, ToolchainRecord, WasmProducers};
+use contents;
+use macho;
+use object::{self, pe, Object, ObjectSection};
+use std::fmt;
+use std::fs;
+use std::io::{self, Read};
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// A builder for scanning many shared libraries, executables, and directory
+/// trees in one go.
+///
+/// Files, and `.dSYM` bundles, are inspected just like with `Options`, with a
+/// `ScannedFile` for each architecture of universal binaries, and for each
+/// member of static libraries with DWARF. Other directories are walked
+/// recursively, and the files within them are only inspected if their magic
+/// number says they are an object file that `dwprod` understands; anything
+/// else is skipped quietly. The `.dSYM` bundles within them are skipped when
+/// the binary they belong to is next to them, as its debug info is found in
+/// the bundle and reported along with it anyway. Split DWARF `.dwo` files and
+/// `.dwp` packages are skipped too, as they are only read through the
+/// skeleton units of the binaries they belong to. Symbolic links to files are
+/// followed, but symbolic links to directories are not, so that walks always
+/// terminate.
+///
+/// With the `rayon` feature, files and the compilation units within each file
+/// are inspected in parallel, but results are still returned in a
+/// deterministic order.
+#[derive(Default, Debug)]
+pub struct Scan {
+    targets: Vec<Target>,
+    debug_dirs: Vec<PathBuf>,
+    toolchain_fallback: bool,
+    type_units: bool,
+    lenient: bool,
+    sequential: bool,
+}
+
+/// The results of inspecting one of the files found by a `Scan`.
+#[derive(Debug)]
+pub struct ScannedFile {
+    path: PathBuf,
+    member: Option<String>,
+    arch: Option<String>,
+    wasm_producers: Option<WasmProducers>,
+    wasm_producers_error: Option<Arc<Error>>,
+    toolchain_records: Vec<ToolchainRecord>,
+    results: Vec<Result<CompilationUnit>>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Scan {
+    /// Construct a new, empty `Scan`.
+    pub fn new() -> Scan {
+        Scan::default()
+    }
+
+    /// Add a file or directory to scan.
+    pub fn path<P: AsRef<Path>>(mut self, path: P) -> Scan {
+        self.targets.push(Target::Path(path.as_ref().into()));
+        self
+    }
+
+    /// Add a file that has already been read into memory, such as one read
+    /// from standard input, to scan. Its results are reported under `name`.
+    /// See `Options::from_vec`.
+    pub fn data<P: AsRef<Path>>(mut self, name: P, data: Vec<u8>) -> Scan {
+        self.targets.push(Target::Data(name.as_ref().into(), data));
+        self
+    }
+
+    /// Add a directory to search for separate debug info files. See
+    /// `Options::debug_search_dir`.
+    pub fn debug_search_dir<P: AsRef<Path>>(mut self, dir: P) -> Scan {
+        self.debug_dirs.push(dir.as_ref().into());
+        self
+    }
+
+    /// Whether to fall back to toolchain records outside of DWARF for files
+    /// without any DWARF. See `Options::toolchain_fallback`.
+    pub fn toolchain_fallback(mut self, fallback: bool) -> Scan {
+        self.toolchain_fallback = fallback;
+        self
+    }
+
+    /// Whether to include type units. See `Options::type_units`.
+    pub fn type_units(mut self, type_units: bool) -> Scan {
+        self.type_units = type_units;
+        self
+    }
+
+    /// Whether to carry on past units that can't be read, recording them as
+    /// diagnostics instead of errors. See `Options::lenient`.
+    pub fn lenient(mut self, lenient: bool) -> Scan {
+        self.lenient = lenient;
+        self
+    }
+
+    /// Whether to inspect files and compilation units in parallel, which is
+    /// the default. The results are the same either way.
+    #[cfg(feature = "rayon")]
+    pub fn parallel(mut self, parallel: bool) -> Scan {
+        self.sequential = !parallel;
+        self
+    }
+
+    /// Find the files to inspect: each of the configured targets that is a
+    /// file or in memory, and every object file within each of the configured
+    /// paths that is a directory, in order.
+    fn files(&mut self) -> Vec<Found> {
+        let mut files = vec![];
+        for target in mem::take(&mut self.targets) {
+            match target {
+                Target::Path(ref path) if path.is_dir() && !macho::is_dsym_bundle(path) => {
+                    walk(path, &mut files)
+                }
+                target => files.push(Ok(target)),
+            }
+        }
+        files
+    }
+
+    /// Finish configuring, and inspect every file.
+    ///
+    /// Files are returned in the order their paths were added, with the
+    /// contents of each directory sorted by path, and the architectures of
+    /// each universal binary and the members of each archive in order. An
+    /// archive member that can't be read is returned with the error, and the
+    /// following members are still inspected. A directory that can't be read
+    /// is returned in place of its contents, with the error.
+    pub fn run(mut self) -> Vec<ScannedFile> {
+        let files = self.files();
+        let scan = |file: Found| match file {
+            Ok(target) => self.scan_file(target),
+            Err((path, e)) => vec![ScannedFile {
+                path,
+                member: None,
+                arch: None,
+                wasm_producers: None,
+                wasm_producers_error: None,
+                toolchain_records: vec![],
+                results: vec![Err(e)],
+                diagnostics: vec![],
+            }],
+        };
+
+        #[cfg(feature = "rayon")]
+        {
+            use rayon::prelude::*;
+            if !self.sequential {
+                // `collect` keeps the results in the same order as the files.
+                let scanned: Vec<_> = files.into_par_iter().map(scan).collect();
+                return scanned.into_iter().flatten().collect();
+            }
+        }
+
+        files.into_iter().flat_map(scan).collect()
+    }
+
+    fn scan_file(&self, target: Target) -> Vec<ScannedFile> {
+        let (path, mut opts) = match target {
+            Target::Path(path) => {
+                let opts = Options::new(&path);
+                (path, opts)
+            }
+            Target::Data(name, data) => (name, Options::from_vec(data)),
+        };
+        for dir in &self.debug_dirs {
+            opts = opts.debug_search_dir(dir);
+        }
+        opts = opts
+            .toolchain_fallback(self.toolchain_fallback)
+            .type_units(self.type_units)
+            .lenient(self.lenient);
+
+        let parallel = !self.sequential;
+        match opts.load_each() {
+            Ok(all) => all
+                .into_iter()
+                .map(|loaded| {
+                    let (wasm_producers, wasm_producers_error, toolchain_records, results) =
+                        match loaded.result {
+                            Ok(info) => (
+                                info.wasm_producers().cloned(),
+                                info.wasm_producers_error.clone(),
+                                info.toolchain_records().to_vec(),
+                                info.compilation_units().collect_results(parallel),
+                            ),
+                            Err(e) => (None, None, vec![], (vec![Err(e)], vec![])),
+                        };
+                    let (results, diagnostics) = results;
+                    ScannedFile {
+                        path: path.clone(),
+                        member: loaded.member,
+                        arch: loaded.arch,
+                        wasm_producers,
+                        wasm_producers_error,
+                        toolchain_records,
+                        results,
+                        diagnostics,
+                    }
+                })
+                .collect(),
+            Err(e) => vec![ScannedFile {
+                path,
+                member: None,
+                arch: None,
+                wasm_producers: None,
+                wasm_producers_error: None,
+                toolchain_records: vec![],
+                results: vec![Err(e)],
+                diagnostics: vec![],
+            }],
+        }
+    }
+}
+
+impl ScannedFile {
+    /// The path of the inspected file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The name of the archive member that was inspected, or `None` if the
+    /// file isn't an archive.
+    pub fn member(&self) -> Option<&str> {
+        self.member.as_deref()
+    }
+
+    /// The architecture of the universal binary slice that was inspected, or
+    /// `None` if the file isn't a universal binary.
+    pub fn arch(&self) -> Option<&str> {
+        self.arch.as_deref()
+    }
+
+    /// The `producers` section of the inspected file, if it is a WebAssembly
+    /// module that has one. See `DebugInfo::wasm_producers`.
+    pub fn wasm_producers(&self) -> Option<&WasmProducers> {
+        self.wasm_producers.as_ref()
+    }
+
+    /// The error encountered parsing the `producers` section of the inspected
+    /// file, if it is a WebAssembly module where that is malformed. See
+    /// `DebugInfo::wasm_producers_error`.
+    pub fn wasm_producers_error(&self) -> Option<&Error> {
+        self.wasm_producers_error.as_deref()
+    }
+
+    /// The toolchain records outside of DWARF, if the file has no DWARF and
+    /// `Scan::toolchain_fallback` is enabled. See `DebugInfo::toolchain_records`.
+    pub fn toolchain_records(&self) -> &[ToolchainRecord] {
+        &self.toolchain_records
+    }
+
+    /// Each compilation unit in the file, or the error encountered reading
+    /// it, in order. If the file couldn't be read at all, this is a single
+    /// error for the whole file.
+    pub fn results(&self) -> &[Result<CompilationUnit>] {
+        &self.results
+    }
+
+    /// The units that couldn't be read from the file, if `Scan::lenient` is
+    /// enabled. Otherwise, this is empty, and they are among the `results`.
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    /// The compilation units that could be read from the file.
+    pub fn units(&self) -> impl Iterator<Item = &CompilationUnit> {
+        self.results.iter().filter_map(|result| result.as_ref().ok())
+    }
+
+    /// The errors encountered reading the file.
+    pub fn errors(&self) -> impl Iterator<Item = &Error> {
+        self.results.iter().filter_map(|result| result.as_ref().err())
+    }
+
+    /// The `DW_AT_producer` of each compilation unit that has one.
+    pub fn producers(&self) -> impl Iterator<Item = &str> {
+        self.units()
+            .filter_map(|unit| unit.producer())
+            .map(|producer| producer.raw())
+    }
+}
+
+/// Something to scan: a file or directory, or a named file in memory.
+enum Target {
+    Path(PathBuf),
+    Data(PathBuf, Vec<u8>),
+}
+
+impl fmt::Debug for Target {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Target::Path(ref path) => f.debug_tuple("Path").field(path).finish(),
+            // Don't dump the whole binary.
+            Target::Data(ref name, ref data) => {
+                write!(f, "Data({:?}, {} bytes)", name, data.len())
+            }
+        }
+    }
+}
+
+/// Either a file to inspect, or a directory that couldn't be read.
+type Found = ::std::result::Result<Target, (PathBuf, Error)>;
+
+/// Recursively collect the object files within `dir`, sorted by path so that
+/// results don't depend on the order the file system lists them in.
+fn walk(dir: &Path, files: &mut Vec<Found>) {
+    let entries = match read_dir_sorted(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            files.push(Err((dir.into(), e)));
+            return;
+        }
+    };
+
+    for &(ref path, file_type) in &entries {
+        if file_type.is_dir() {
+            // The DWARF in a binary's `.dSYM` bundle is already reported
+            // along with the binary.
+            if is_dsym_of_sibling(path) {
+                continue;
+            }
+            walk(path, files);
+        } else if file_type.is_file() || (file_type.is_symlink() && path.is_file()) {
+            // Files that can't even be opened to check their magic number are
+            // skipped along with the other non-object files.
+            if let Ok(true) = is_object_file(path) {
+                if !is_split_dwarf_file(path) {
+                    files.push(Ok(Target::Path(path.clone())));
+                }
+            }
+        }
+    }
+}
+
+/// Whether `path` is the `.dSYM` bundle of an object file next to it, such as
+/// `app.dSYM` for `app`.
+fn is_dsym_of_sibling(path: &Path) -> bool {
+    if path.extension() != Some("dSYM".as_ref()) {
+        return false;
+    }
+    let binary = path.with_extension("");
+    binary.is_file() && is_object_file(&binary).unwrap_or(false)
+}
+
+/// Whether the file at `path` holds split DWARF, like a `.dwo` file or a `.dwp`
+/// package: it has sections with `.dwo` names, such as `.debug_info.dwo`, but
+/// no `.debug_info` of its own.
+fn is_split_dwarf_file(path: &Path) -> bool {
+    let data = match contents::load(path) {
+        Ok(data) => data,
+        Err(_) => return false,
+    };
+    let file = match object::File::parse(&*data) {
+        Ok(file) => file,
+        Err(_) => return false,
+    };
+    let mut split = false;
+    for section in file.sections() {
+        match section.name() {
+            Ok(".debug_info") | Ok(".zdebug_info") => return false,
+            Ok(name) if name.ends_with(".dwo") => split = true,
+            _ => {}
+        }
+    }
+    split
+}
+
+fn read_dir_sorted(dir: &Path) -> Result<Vec<(PathBuf, fs::FileType)>> {
+    let mut entries = vec![];
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        entries.push((entry.path(), entry.file_type()?));
+    }
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(entries)
+}
+
+/// Whether the file at `path` starts with the magic number of an object file
+/// format that we support.
+fn is_object_file(path: &Path) -> io::Result<bool> {
+    let mut magic = vec![];
+    fs::File::open(path)?.take(20).read_to_end(&mut magic)?;
+    Ok(is_object_magic(&magic))
+}
+
+fn is_object_magic(magic: &[u8]) -> bool {
+    match magic {
+        // Universal Mach-O binaries share their magic number with Java class
+        // files, whose next field is a version number of at least 45. A
+        // universal binary's next field is its number of architectures.
+        [0xca, 0xfe, 0xba, 0xbe, a, b, c, d, ..] | [0xca, 0xfe, 0xba, 0xbf, a, b, c, d, ..] => {
+            u32::from_be_bytes([*a, *b, *c, *d]) < 45
+        }
+        // ELF.
+        [0x7f, b'E', b'L', b'F', ..] => true,
+        // 32- and 64-bit Mach-O, in either byte order.
+        [0xfe, 0xed, 0xfa, 0xce, ..]
+        | [0xce, 0xfa, 0xed, 0xfe, ..]
+        | [0xfe, 0xed, 0xfa, 0xcf, ..]
+        | [0xcf, 0xfa, 0xed, 0xfe, ..] => true,
+        // PE, starting with the MS-DOS stub's magic.
+        [b'M', b'Z', ..] => true,
+        // `ar` archives, and thin archives.
+        [b'!', b'<', b'a', b'r', b'c', b'h', b'>', b'\n', ..]
+        | [b'!', b'<', b't', b'h', b'i', b'n', b'>', b'\n', ..] => true,
+        // WebAssembly.
+        [0x00, b'a', b's', b'm', ..] => true,
+        // COFF object files with an anonymous object header, as written with
+        // `-mbig-obj`.
+        [0x00, 0x00, 0xff, 0xff, 0x02, 0x00, ..] => true,
+        _ => is_coff_object_magic(magic),
+    }
+}
+
+/// COFF object files, such as MinGW's, have no magic number of their own. They
+/// start with the machine type, and unlike PE images have no optional header.
+fn is_coff_object_magic(magic: &[u8]) -> bool {
+    if magic.len() < 20 || magic[16..18] != [0, 0] {
+        return false;
+    }
+    matches!(
+        u16::from_le_bytes([magic[0], magic[1]]),
+        pe::IMAGE_FILE_MACHINE_I386
+            | pe::IMAGE_FILE_MACHINE_AMD64
+            | pe::IMAGE_FILE_MACHINE_ARMNT
+            | pe::IMAGE_FILE_MACHINE_ARM64
+    )
+}
@@ -0,0 +1,86 @@
+//! Following the skeleton units produced by `-gsplit-dwarf` to the split units
+//! holding their full debug info, in either `.dwo` files or a `.dwp` package.
+
+use super::{load_section, Error, Result};
+use contents::{self, Contents};
+use gimli::{Dwarf, DwarfPackage, Reader as _, Unit};
+use object;
+use reader::{Data, Reader};
+use std::path::{Path, PathBuf};
+use unit::RootAttrs;
+
+/// Read the `.dwp` package that sits next to the given file, such as `app.dwp`
+/// for `app`, if there is one.
+pub fn read_dwp(path: &Path) -> Result<Option<Contents>> {
+    let mut dwp = path.as_os_str().to_owned();
+    dwp.push(".dwp");
+    Ok(contents::load_if_exists(Path::new(&dwp))?)
+}
+
+/// If `unit` is a skeleton unit, get the root entry attributes of its split
+/// unit.
+///
+/// The split unit is looked up in the `.dwp` package first, if there is one,
+/// and otherwise in the `.dwo` file named by the skeleton unit, relative to
+/// its `DW_AT_comp_dir`.
+pub fn split_attrs(
+    path: Option<&Path>,
+    dwarf: &Dwarf<Reader>,
+    dwp: Option<&DwarfPackage<Reader>>,
+    unit: &Unit<Reader>,
+) -> Result<Option<RootAttrs>> {
+    let dwo_id = match unit.dwo_id {
+        Some(dwo_id) => dwo_id,
+        None => return Ok(None),
+    };
+
+    if let Some(dwp) = dwp {
+        if let Some(split) = dwp.find_cu(dwo_id, dwarf)? {
+            let mut headers = split.units();
+            return match headers.next()? {
+                Some(header) => Ok(Some(RootAttrs::read(&split, &split.unit(header)?)?)),
+                None => Ok(None),
+            };
+        }
+    }
+
+    let dwo_name = match unit.dwo_name()? {
+        Some(value) => dwarf.attr_string(unit, value)?,
+        None => return Ok(None),
+    };
+    let dwo_path = dwo_path(path, unit, &dwo_name.to_string_lossy()?)?;
+
+    let data = match contents::load(&dwo_path) {
+        Ok(data) => data,
+        Err(error) => return Err(Error::SplitDwarfFile { path: dwo_path, error }),
+    };
+    let data = Data::new(data);
+    let file = object::File::parse(&*data)?;
+    let mut split = Dwarf::load(|id| load_section(&data, &file, id, id.dwo_name()))?;
+    split.make_dwo(dwarf);
+
+    let mut headers = split.units();
+    while let Some(header) = headers.next()? {
+        let split_unit = split.unit(header)?;
+        if split_unit.dwo_id == Some(dwo_id) {
+            return Ok(Some(RootAttrs::read(&split, &split_unit)?));
+        }
+    }
+
+    Err(Error::MissingSplitUnit {
+        path: dwo_path,
+        dwo_id: dwo_id.0,
+    })
+}
+
+/// Resolve a `.dwo` name against the skeleton unit's `DW_AT_comp_dir`. Without
+/// a compilation directory, the name is relative to the directory containing
+/// the file being inspected, or to the current directory for files that were
+/// loaded from memory.
+fn dwo_path(path: Option<&Path>, unit: &Unit<Reader>, dwo_name: &str) -> Result<PathBuf> {
+    let dir = match unit.comp_dir {
+        Some(ref comp_dir) => PathBuf::from(&*comp_dir.to_string_lossy()?),
+        None => path.and_then(Path::parent).map(Path::to_path_buf).unwrap_or_default(),
+    };
+    Ok(dir.join(dwo_name))
+}
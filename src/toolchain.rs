@@ -0,0 +1,183 @@
+//! Toolchain records outside of DWARF, which stripped binaries still carry:
+//! the compiler and linker identification strings in `.comment`, and the
+//! properties in `.note.gnu.property` that say which hardening features the
+//! toolchain compiled everything with.
+
+use super::Result;
+use object::elf::{self, FileHeader32, FileHeader64};
+use object::read::elf::{FileHeader, NoteIterator};
+use object::{self, Endianness, Object, ObjectSection};
+use std::fmt;
+
+/// A record of the toolchain that built a file, from outside its DWARF.
+///
+/// These are only reported when `Options::toolchain_fallback` is enabled and
+/// the file has no DWARF of its own.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ToolchainRecord {
+    source: RecordSource,
+    text: String,
+}
+
+/// Where a `ToolchainRecord` came from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RecordSource {
+    /// One of the strings in the `.comment` section, which compilers and
+    /// linkers identify themselves in, such as `GCC: (GNU) 12.2.0` or
+    /// `Linker: LLD 17.0.6`.
+    Comment,
+    /// A property in the `.note.gnu.property` section, such as `x86 feature:
+    /// IBT, SHSTK`, in the same format as `readelf --notes`.
+    GnuProperty,
+}
+
+impl ToolchainRecord {
+    /// Where this record came from.
+    pub fn source(&self) -> RecordSource {
+        self.source
+    }
+
+    /// The record itself.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+}
+
+impl fmt::Display for ToolchainRecord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.source, self.text)
+    }
+}
+
+impl RecordSource {
+    /// The name of the section that records from this source come from.
+    pub fn section_name(&self) -> &'static str {
+        match *self {
+            RecordSource::Comment => ".comment",
+            RecordSource::GnuProperty => ".note.gnu.property",
+        }
+    }
+}
+
+impl fmt::Display for RecordSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.section_name())
+    }
+}
+
+/// Get the toolchain records in `file`, in order, without duplicates.
+pub fn records(file: &object::File) -> Result<Vec<ToolchainRecord>> {
+    let mut records = vec![];
+    let mut push = |source, text: String| {
+        let record = ToolchainRecord { source, text };
+        if !record.text.is_empty() && !records.contains(&record) {
+            records.push(record);
+        }
+    };
+
+    // Linkers merge the identical strings from each object file, but not the
+    // different ones, so `.comment` is a list of NUL-terminated strings.
+    if let Some(section) = file.section_by_name(".comment") {
+        for string in section.data()?.split(|&b| b == 0) {
+            push(RecordSource::Comment, String::from_utf8_lossy(string).trim().into());
+        }
+    }
+
+    if file.format() == object::BinaryFormat::Elf {
+        if let Some(section) = file.section_by_name(".note.gnu.property") {
+            let endian = file.endianness();
+            let data = section.data()?;
+            let properties = if file.is_64() {
+                gnu_properties::<FileHeader64<Endianness>>(endian, section.align(), data)?
+            } else {
+                gnu_properties::<FileHeader32<Endianness>>(endian, section.align(), data)?
+            };
+            for property in properties {
+                push(RecordSource::GnuProperty, property);
+            }
+        }
+    }
+
+    Ok(records)
+}
+
+/// Describe each GNU property in the given notes.
+fn gnu_properties<Elf>(endian: Endianness, align: u64, data: &[u8]) -> Result<Vec<String>>
+where
+    Elf: FileHeader<Endian = Endianness>,
+    Elf::Word: From<u32>,
+{
+    let align: u32 = if align == 8 { 8 } else { 4 };
+    let mut notes = NoteIterator::<Elf>::new(endian, align.into(), data)?;
+    let mut properties = vec![];
+    while let Some(note) = notes.next()? {
+        let mut iter = match note.gnu_properties(endian) {
+            Some(iter) => iter,
+            None => continue,
+        };
+        while let Some(property) = iter.next()? {
+            properties.push(describe(endian, property.pr_type(), property.pr_data()));
+        }
+    }
+    Ok(properties)
+}
+
+/// Describe a GNU property like `readelf --notes` does.
+fn describe(endian: Endianness, pr_type: u32, data: &[u8]) -> String {
+    let flags = |name: &str, bits: &[(u32, &str)]| {
+        let value = match data.len() {
+            4 => {
+                let mut bytes = [0; 4];
+                bytes.copy_from_slice(data);
+                match endian {
+                    Endianness::Little => u32::from_le_bytes(bytes),
+                    Endianness::Big => u32::from_be_bytes(bytes),
+                }
+            }
+            _ => return format!("{}: <corrupt length: {:#x}>", name, data.len()),
+        };
+        let mut set: Vec<String> = bits
+            .iter()
+            .filter(|&&(bit, _)| value & bit != 0)
+            .map(|&(_, bit_name)| bit_name.to_string())
+            .collect();
+        let unknown = bits.iter().fold(value, |value, &(bit, _)| value & !bit);
+        if unknown != 0 {
+            set.push(format!("<unknown: {:x}>", unknown));
+        }
+        if set.is_empty() {
+            set.push("<None>".into());
+        }
+        format!("{}: {}", name, set.join(", "))
+    };
+
+    let x86_isa = [
+        (elf::GNU_PROPERTY_X86_ISA_1_BASELINE, "x86-64-baseline"),
+        (elf::GNU_PROPERTY_X86_ISA_1_V2, "x86-64-v2"),
+        (elf::GNU_PROPERTY_X86_ISA_1_V3, "x86-64-v3"),
+        (elf::GNU_PROPERTY_X86_ISA_1_V4, "x86-64-v4"),
+    ];
+    match pr_type {
+        elf::GNU_PROPERTY_X86_FEATURE_1_AND => flags(
+            "x86 feature",
+            &[
+                (elf::GNU_PROPERTY_X86_FEATURE_1_IBT, "IBT"),
+                (elf::GNU_PROPERTY_X86_FEATURE_1_SHSTK, "SHSTK"),
+            ],
+        ),
+        elf::GNU_PROPERTY_X86_ISA_1_NEEDED => flags("x86 ISA needed", &x86_isa),
+        elf::GNU_PROPERTY_X86_ISA_1_USED => flags("x86 ISA used", &x86_isa),
+        elf::GNU_PROPERTY_AARCH64_FEATURE_1_AND => flags(
+            "AArch64 feature",
+            &[
+                (elf::GNU_PROPERTY_AARCH64_FEATURE_1_BTI, "BTI"),
+                (elf::GNU_PROPERTY_AARCH64_FEATURE_1_PAC, "PAC"),
+            ],
+        ),
+        elf::GNU_PROPERTY_NO_COPY_ON_PROTECTED => "no copy on protected".into(),
+        _ => {
+            let hex: String = data.iter().map(|b| format!("{:02x}", b)).collect();
+            format!("<type {:#x}>: {}", pr_type, hex)
+        }
+    }
+}
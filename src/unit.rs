@@ -0,0 +1,285 @@
+//! Per-compilation-unit records: where each unit lives, and what its root
+//! entry says about the source file and the compiler that built it.
+
+use super::{Error, Result};
+use reader::Reader;
+use gimli::{
+    Attribute, AttributeValue, Dwarf, Reader as _, Section, Unit, UnitSectionOffset, UnitType,
+};
+use producer::Producer;
+use std::fmt;
+use std::path::Path;
+
+/// A compilation unit within the configured file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CompilationUnit {
+    kind: UnitKind,
+    offset: u64,
+    version: u16,
+    address_size: u8,
+    name: Option<String>,
+    comp_dir: Option<String>,
+    language: Option<gimli::DwLang>,
+    producer: Option<Producer>,
+}
+
+/// What kind of unit a `CompilationUnit` is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum UnitKind {
+    /// A compilation unit, including the skeleton units of split DWARF.
+    Compile,
+    /// A type unit, which holds a single type that several compilation units
+    /// share, either in a DWARF 4 `.debug_types` section or as a DWARF 5
+    /// `DW_UT_type` unit. These are only yielded when `Options::type_units`
+    /// is enabled.
+    Type,
+}
+
+impl CompilationUnit {
+    /// What kind of unit this is.
+    pub fn kind(&self) -> UnitKind {
+        self.kind
+    }
+
+    /// The offset of the unit's header within the `.debug_info` section, or
+    /// within the `.debug_types` section for DWARF 4 type units.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// The unit's DWARF version.
+    pub fn version(&self) -> u16 {
+        self.version
+    }
+
+    /// The size of a target address, in bytes.
+    pub fn address_size(&self) -> u8 {
+        self.address_size
+    }
+
+    /// The unit's `DW_AT_name`, usually the path of its primary source file.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// The unit's `DW_AT_comp_dir`, the directory it was compiled in.
+    pub fn comp_dir(&self) -> Option<&str> {
+        self.comp_dir.as_deref()
+    }
+
+    /// The unit's `DW_AT_language`, as a `DW_LANG_*` code.
+    pub fn language(&self) -> Option<u16> {
+        self.language.map(|lang| lang.0)
+    }
+
+    /// The name of the unit's `DW_AT_language` constant, such as
+    /// `DW_LANG_Rust`, if it is a known language.
+    pub fn language_name(&self) -> Option<&'static str> {
+        self.language.and_then(|lang| lang.static_string())
+    }
+
+    /// The unit's `DW_AT_producer`.
+    pub fn producer(&self) -> Option<&Producer> {
+        self.producer.as_ref()
+    }
+}
+
+/// A unit that couldn't be read, when `Options::lenient` is enabled.
+#[derive(Debug)]
+pub struct Diagnostic {
+    offset: UnitSectionOffset,
+    error: Error,
+}
+
+impl Diagnostic {
+    /// The name of the section that the unit is in, which is `.debug_info`,
+    /// or `.debug_types` for DWARF 4 type units.
+    pub fn section(&self) -> &'static str {
+        section_name(self.offset)
+    }
+
+    /// The offset of the unit's header within its section.
+    pub fn offset(&self) -> u64 {
+        section_offset(self.offset)
+    }
+
+    /// The error encountered reading the unit.
+    pub fn error(&self) -> &Error {
+        &self.error
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unit at {}+{:#x}: {}", self.section(), self.offset(), self.error)
+    }
+}
+
+/// Record the error encountered reading the unit at `offset`.
+pub fn diagnostic(offset: UnitSectionOffset, error: Error) -> Diagnostic {
+    Diagnostic { offset, error }
+}
+
+/// Add the unit's location to the error encountered reading the unit at
+/// `offset` within the file at `path`.
+pub fn unit_error(path: Option<&Path>, offset: UnitSectionOffset, error: Error) -> Error {
+    Error::Unit {
+        path: path.map(Path::to_path_buf),
+        section: section_name(offset),
+        offset: section_offset(offset),
+        error: Box::new(error),
+    }
+}
+
+/// The attributes that we report from a unit's root entry.
+#[derive(Debug, Default)]
+pub struct RootAttrs {
+    pub name: Option<String>,
+    pub comp_dir: Option<String>,
+    pub language: Option<gimli::DwLang>,
+    pub producer: Option<String>,
+}
+
+impl RootAttrs {
+    /// Read the attributes of the given unit's root entry.
+    pub fn read(dwarf: &Dwarf<Reader>, unit: &Unit<Reader>) -> Result<RootAttrs> {
+        let mut tree = unit.entries_tree(None)?;
+        let root = tree.root()?;
+        let code = root.entry().code();
+        let mut attrs = root.entry().attrs();
+
+        let mut root_attrs = RootAttrs::default();
+        while let Some(attr) = attrs.next()? {
+            match attr.name() {
+                gimli::DW_AT_name => {
+                    root_attrs.name = Some(attr_string(dwarf, unit, code, &attr)?)
+                }
+                gimli::DW_AT_comp_dir => {
+                    root_attrs.comp_dir = Some(attr_string(dwarf, unit, code, &attr)?)
+                }
+                gimli::DW_AT_producer => {
+                    root_attrs.producer = Some(attr_string(dwarf, unit, code, &attr)?)
+                }
+                gimli::DW_AT_language => {
+                    if let AttributeValue::Language(lang) = attr.value() {
+                        root_attrs.language = Some(lang);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(root_attrs)
+    }
+
+    /// Fill in any attributes that are missing from `self` with the ones from
+    /// `other`, such as a skeleton unit's from its split unit.
+    pub fn fill_from(&mut self, other: RootAttrs) {
+        self.name = self.name.take().or(other.name);
+        self.comp_dir = self.comp_dir.take().or(other.comp_dir);
+        self.language = self.language.or(other.language);
+        self.producer = self.producer.take().or(other.producer);
+    }
+}
+
+/// Get the kind of unit that a header with the given unit type is for.
+pub fn kind(unit_type: UnitType<usize>) -> UnitKind {
+    match unit_type {
+        UnitType::Type { .. } | UnitType::SplitType { .. } => UnitKind::Type,
+        _ => UnitKind::Compile,
+    }
+}
+
+/// Make the record for the given unit, with the given root entry attributes.
+pub fn compilation_unit(unit: &Unit<Reader>, attrs: RootAttrs) -> CompilationUnit {
+    CompilationUnit {
+        kind: kind(unit.header.type_()),
+        offset: section_offset(unit.header.offset()),
+        version: unit.header.version(),
+        address_size: unit.header.address_size(),
+        name: attrs.name,
+        comp_dir: attrs.comp_dir,
+        language: attrs.language,
+        producer: attrs.producer.map(Producer::parse),
+    }
+}
+
+/// Get the name of the section that an offset is within.
+fn section_name(offset: UnitSectionOffset) -> &'static str {
+    match offset {
+        UnitSectionOffset::DebugInfoOffset(_) => ".debug_info",
+        UnitSectionOffset::DebugTypesOffset(_) => ".debug_types",
+    }
+}
+
+/// Get an offset within `.debug_info` or `.debug_types` as a number.
+fn section_offset(offset: UnitSectionOffset) -> u64 {
+    match offset {
+        UnitSectionOffset::DebugInfoOffset(offset) => offset.0 as u64,
+        UnitSectionOffset::DebugTypesOffset(offset) => offset.0 as u64,
+    }
+}
+
+/// Read a string-valued attribute of the entry with the abbreviation `code`.
+fn attr_string(
+    dwarf: &Dwarf<Reader>,
+    unit: &Unit<Reader>,
+    code: u64,
+    attr: &Attribute<Reader>,
+) -> Result<String> {
+    let value = attr.value();
+    match value {
+        AttributeValue::DebugStrRef(_)
+        | AttributeValue::DebugStrRefSup(_)
+        | AttributeValue::DebugStrOffsetsIndex(_)
+        | AttributeValue::DebugLineStrRef(_) => {
+            check_string_sections(dwarf, &value)?;
+            let s = dwarf.attr_string(unit, value)?;
+            Ok(s.to_string()?.into())
+        }
+        AttributeValue::String(data) | AttributeValue::Block(data) => Ok(data.to_string()?.into()),
+        _ => {
+            // The entry was parsed with this abbreviation, which is where the
+            // attribute came from.
+            let form = unit
+                .abbreviations
+                .get(code)
+                .and_then(|abbrev| {
+                    let mut specs = abbrev.attributes().iter();
+                    specs.find(|spec| spec.name() == attr.name())
+                })
+                .map(|spec| spec.form())
+                .expect("the entry's abbreviation should have its attributes");
+            Err(Error::UnsupportedForm {
+                attribute: Some(attr.name()),
+                form,
+            })
+        }
+    }
+}
+
+/// Ensure that the sections needed to resolve the given string attribute value
+/// are present, rather than failing with an opaque out-of-bounds read.
+fn check_string_sections(
+    dwarf: &Dwarf<Reader>,
+    value: &AttributeValue<Reader>,
+) -> Result<()> {
+    let debug_str = dwarf.debug_str.reader();
+    let debug_str_offsets = dwarf.debug_str_offsets.reader();
+    let debug_line_str = dwarf.debug_line_str.reader();
+
+    let missing = match *value {
+        AttributeValue::DebugStrRef(_) if debug_str.is_empty() => ".debug_str",
+        AttributeValue::DebugStrRefSup(_) if dwarf.sup().is_none() => {
+            return Err(Error::MissingSupplementaryFile);
+        }
+        AttributeValue::DebugStrOffsetsIndex(_) if debug_str_offsets.is_empty() => {
+            ".debug_str_offsets"
+        }
+        AttributeValue::DebugStrOffsetsIndex(_) if debug_str.is_empty() => ".debug_str",
+        AttributeValue::DebugLineStrRef(_) if debug_line_str.is_empty() => ".debug_line_str",
+        _ => return Ok(()),
+    };
+
+    Err(Error::MissingSection(missing.into()))
+}
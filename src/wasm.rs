@@ -0,0 +1,118 @@
+//! WebAssembly modules, which keep their DWARF in custom sections named after
+//! the usual ELF sections, and describe the toolchain that built them in a
+//! `producers` custom section.
+
+use super::{Error, Result};
+use gimli::{EndianSlice, LittleEndian, Reader as _};
+use std::str;
+
+type Slice<'a> = EndianSlice<'a, LittleEndian>;
+
+/// The id of custom sections, which are the only kind we look at.
+const CUSTOM_SECTION: u8 = 0;
+
+/// The toolchain that built a WebAssembly module, as recorded in its
+/// `producers` custom section.
+///
+/// Each field lists the tools of one kind, in order, such as `Rust` for the
+/// source language, and `rustc` and `wasm-bindgen` for the tools that
+/// processed the module.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WasmProducers {
+    language: Vec<WasmTool>,
+    processed_by: Vec<WasmTool>,
+    sdk: Vec<WasmTool>,
+}
+
+/// A tool listed in a WebAssembly module's `producers` section.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WasmTool {
+    /// The tool's name, such as `rustc` or `clang`.
+    pub name: String,
+    /// The tool's version, which may be empty, such as
+    /// `1.75.0 (82e1608df 2023-12-21)`.
+    pub version: String,
+}
+
+impl WasmProducers {
+    /// The source languages the module was written in, from the `language`
+    /// field.
+    pub fn language(&self) -> &[WasmTool] {
+        &self.language
+    }
+
+    /// The compilers and other tools that processed the module, from the
+    /// `processed-by` field.
+    pub fn processed_by(&self) -> &[WasmTool] {
+        &self.processed_by
+    }
+
+    /// The SDKs the module was built with, such as Emscripten, from the `sdk`
+    /// field.
+    pub fn sdk(&self) -> &[WasmTool] {
+        &self.sdk
+    }
+}
+
+/// Whether `data` starts with the WebAssembly magic number.
+pub fn is_wasm(data: &[u8]) -> bool {
+    data.starts_with(b"\0asm")
+}
+
+/// Get the name and contents of each custom section in the given module, in
+/// order.
+pub fn custom_sections(data: &[u8]) -> Result<Vec<(&str, &[u8])>> {
+    if data.len() < 8 || !is_wasm(data) {
+        return Err("invalid WebAssembly module header".into());
+    }
+    let mut version = [0; 4];
+    version.copy_from_slice(&data[4..8]);
+    let version = u32::from_le_bytes(version);
+    if version != 1 {
+        return Err(Error::UnsupportedFormat(format!("WebAssembly version {}", version)));
+    }
+
+    let mut input = Slice::new(&data[8..], LittleEndian);
+    let mut sections = vec![];
+    while !input.is_empty() {
+        let id = input.read_u8()?;
+        let size = input.read_uleb128_u32()?;
+        let mut contents = input.split(size as usize)?;
+        if id == CUSTOM_SECTION {
+            let name = read_name(&mut contents)?;
+            sections.push((name, contents.slice()));
+        }
+    }
+    Ok(sections)
+}
+
+/// Parse the contents of a `producers` custom section. Fields other than the
+/// standard `language`, `processed-by` and `sdk` are ignored.
+pub fn parse_producers(data: &[u8]) -> Result<WasmProducers> {
+    let mut input = Slice::new(data, LittleEndian);
+    let mut producers = WasmProducers::default();
+    for _ in 0..input.read_uleb128_u32()? {
+        let field = read_name(&mut input)?;
+        let mut tools = vec![];
+        for _ in 0..input.read_uleb128_u32()? {
+            tools.push(WasmTool {
+                name: read_name(&mut input)?.into(),
+                version: read_name(&mut input)?.into(),
+            });
+        }
+        match field {
+            "language" => producers.language = tools,
+            "processed-by" => producers.processed_by = tools,
+            "sdk" => producers.sdk = tools,
+            _ => {}
+        }
+    }
+    Ok(producers)
+}
+
+/// Read a name, which is its length in bytes and then UTF-8.
+fn read_name<'a>(input: &mut Slice<'a>) -> Result<&'a str> {
+    let len = input.read_uleb128_u32()?;
+    let name = input.split(len as usize)?;
+    str::from_utf8(name.slice()).map_err(|_| "invalid UTF-8 in WebAssembly name".into())
+}
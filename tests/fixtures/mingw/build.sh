@@ -0,0 +1,18 @@
+#!/bin/sh
+#
+# Rebuild the MinGW fixtures: `main.o`, a COFF object file, and `app.exe`, a PE
+# image, both with DWARF in sections whose names are longer than eight bytes.
+#
+# `main.ll` is `main.c` as LLVM IR with debug info, in the form that
+# `clang -g -O0 -S -emit-llvm --target=x86_64-w64-windows-gnu` emits, and
+# `crt.ll` stands in for the `__main` that MinGW's libgcc provides. They are
+# compiled with LLVM's MinGW backend and linked with GNU ld's `i386pep`
+# emulation, which is MinGW's linker.
+
+set -eu
+cd "$(dirname "$0")"
+
+llc -filetype=obj main.ll -o main.o
+llc -filetype=obj crt.ll -o crt.o
+ld -m i386pep --no-insert-timestamp --entry=main -o app.exe main.o crt.o
+rm crt.o
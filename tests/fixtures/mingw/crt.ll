@@ -0,0 +1,6 @@
+target triple = "x86_64-w64-windows-gnu"
+
+define dso_local void @__main() {
+entry:
+  ret void
+}
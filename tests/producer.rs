@@ -0,0 +1,218 @@
+extern crate dwprod;
+
+use dwprod::{Channel, Compiler, Producer, Version};
+
+fn version(s: &str) -> Version {
+    Version::parse(s).unwrap()
+}
+
+fn assert_parses(raw: &str, compiler: Compiler, expected_version: &str, flags: &[&str]) {
+    let producer = Producer::parse(raw);
+    assert_eq!(producer.raw(), raw);
+    assert_eq!(producer.to_string(), raw);
+    assert_eq!(producer.compiler(), compiler, "compiler of {:?}", raw);
+    assert_eq!(producer.version(), Some(&version(expected_version)), "version of {:?}", raw);
+    assert_eq!(producer.flags(), flags, "flags of {:?}", raw);
+}
+
+#[test]
+fn gcc() {
+    assert_parses(
+        "GNU C 4.8.5 -m64 -mtune=generic -march=x86-64 -g3 -O3 -O2 -O2 -std=gnu11 -fPIC",
+        Compiler::Gcc,
+        "4.8.5",
+        &[
+            "-m64",
+            "-mtune=generic",
+            "-march=x86-64",
+            "-g3",
+            "-O3",
+            "-O2",
+            "-O2",
+            "-std=gnu11",
+            "-fPIC",
+        ],
+    );
+    assert_parses(
+        "GNU C17 12.2.0 -mtune=generic -march=x86-64 -g -O2",
+        Compiler::Gcc,
+        "12.2.0",
+        &["-mtune=generic", "-march=x86-64", "-g", "-O2"],
+    );
+    assert_parses(
+        "GNU C++14 9.4.0 -mtune=generic -march=x86-64 -g -fasynchronous-unwind-tables",
+        Compiler::Gcc,
+        "9.4.0",
+        &["-mtune=generic", "-march=x86-64", "-g", "-fasynchronous-unwind-tables"],
+    );
+    assert_parses(
+        "GNU C 4.8.5 20150623 (Red Hat 4.8.5-44) -mtune=generic -march=x86-64 -g",
+        Compiler::Gcc,
+        "4.8.5",
+        &["-mtune=generic", "-march=x86-64", "-g"],
+    );
+    assert_parses(
+        "GNU Fortran2008 12.2.0 -mtune=generic -march=x86-64 -g",
+        Compiler::Gcc,
+        "12.2.0",
+        &["-mtune=generic", "-march=x86-64", "-g"],
+    );
+    assert_parses("GNU Go 12.2.0", Compiler::Gcc, "12.2.0", &[]);
+    assert_parses("GNU Objective-C 4.2.1", Compiler::Gcc, "4.2.1", &[]);
+    assert_parses("GNU AS 2.40", Compiler::GnuAssembler, "2.40.0", &[]);
+}
+
+#[test]
+fn clang() {
+    assert_parses(
+        "clang version 17.0.6 (https://github.com/llvm/llvm-project 6009708b4367171ccdbf4b5905cb6a803753fe18)",
+        Compiler::Clang,
+        "17.0.6",
+        &[],
+    );
+    assert_parses(
+        "Ubuntu clang version 14.0.0-1ubuntu1.1",
+        Compiler::Clang,
+        "14.0.0-1ubuntu1.1",
+        &[],
+    );
+    assert_parses(
+        "Android (8490178, based on r450784d) clang version 14.0.6 (https://android.googlesource.com/toolchain/llvm-project 4c603efb0cca074e9238af8b4106c30add4418f6)",
+        Compiler::Clang,
+        "14.0.6",
+        &[],
+    );
+    assert_parses(
+        "clang version 16.0.6 (Fedora 16.0.6-3.fc38) /usr/bin/clang -g -O2 -c foo.c",
+        Compiler::Clang,
+        "16.0.6",
+        &["-g", "-O2", "-c", "foo.c"],
+    );
+    assert_parses(
+        "Apple clang version 15.0.0 (clang-1500.1.0.2.5)",
+        Compiler::AppleClang,
+        "15.0.0",
+        &[],
+    );
+    assert_parses("flang-new version 17.0.6", Compiler::Flang, "17.0.6", &[]);
+}
+
+#[test]
+fn rustc() {
+    let producer = Producer::parse("clang LLVM (rustc version 1.22.0-nightly (088216fb9 2017-09-04))");
+    assert_eq!(producer.compiler(), Compiler::Rustc);
+    assert_eq!(producer.version(), Some(&version("1.22.0-nightly")));
+    assert!(producer.flags().is_empty());
+    let rustc = producer.rustc().unwrap();
+    assert_eq!(rustc.channel, Channel::Nightly);
+    assert_eq!(rustc.commit_hash.as_ref().unwrap(), "088216fb9");
+    assert_eq!(rustc.commit_date.as_ref().unwrap(), "2017-09-04");
+
+    let producer = Producer::parse("clang LLVM (rustc version 1.75.0 (82e1608df 2023-12-21))");
+    assert_eq!(producer.version(), Some(&version("1.75.0")));
+    let rustc = producer.rustc().unwrap();
+    assert_eq!(rustc.channel, Channel::Stable);
+    assert_eq!(rustc.commit_hash.as_ref().unwrap(), "82e1608df");
+    assert_eq!(rustc.commit_date.as_ref().unwrap(), "2023-12-21");
+
+    let producer = Producer::parse("clang LLVM (rustc version 1.76.0-beta.5 (b8ce1fbc5 2024-01-23))");
+    assert_eq!(producer.version(), Some(&version("1.76.0-beta.5")));
+    assert_eq!(producer.rustc().unwrap().channel, Channel::Beta);
+
+    let producer = Producer::parse("clang LLVM (rustc version 1.78.0-dev)");
+    assert_eq!(producer.version(), Some(&version("1.78.0-dev")));
+    let rustc = producer.rustc().unwrap();
+    assert_eq!(rustc.channel, Channel::Dev);
+    assert_eq!(rustc.commit_hash, None);
+    assert_eq!(rustc.commit_date, None);
+
+    assert!(Producer::parse("GNU C 4.8.5").rustc().is_none());
+}
+
+#[test]
+fn go() {
+    assert_parses("Go cmd/compile go1.21.5", Compiler::Go, "1.21.5", &[]);
+    assert_parses("Go cmd/compile go1.20.1; regabi", Compiler::Go, "1.20.1", &["regabi"]);
+    assert_parses("Go cmd/compile go1.21rc2", Compiler::Go, "1.21.0-rc2", &[]);
+}
+
+#[test]
+fn swift() {
+    assert_parses(
+        "Apple Swift version 5.9 (swiftlang-5.9.0.128.108 clang-1500.0.40.1)",
+        Compiler::Swift,
+        "5.9.0",
+        &[],
+    );
+    assert_parses(
+        "Swift version 5.9.2 (swift-5.9.2-RELEASE)",
+        Compiler::Swift,
+        "5.9.2",
+        &[],
+    );
+}
+
+#[test]
+fn intel() {
+    assert_parses(
+        "Intel(R) Fortran Intel(R) 64 Compiler Classic for applications running on Intel(R) 64, Version 2021.10.0 Build 20230609_000000",
+        Compiler::IntelFortran,
+        "2021.10.0",
+        &[],
+    );
+    assert_parses("Intel(R) Fortran 23.0-1496", Compiler::IntelFortran, "23.0.0-1496", &[]);
+    assert_parses(
+        "Intel(R) C Intel(R) 64 Compiler for applications running on Intel(R) 64, Version 19.1.3.304 Build 20200925",
+        Compiler::Intel,
+        "19.1.3",
+        &[],
+    );
+    assert_parses(
+        "Intel(R) oneAPI DPC++/C++ Compiler 2023.2.0 (2023.2.0.20230721)",
+        Compiler::Intel,
+        "2023.2.0",
+        &[],
+    );
+}
+
+#[test]
+fn d_and_zig() {
+    assert_parses(
+        "LDC 1.32.0 (DMD v2.102.2, LLVM 15.0.7)",
+        Compiler::Ldc,
+        "1.32.0",
+        &[],
+    );
+    assert_parses("Digital Mars D v2.105.0", Compiler::Dmd, "2.105.0", &[]);
+    assert_parses("zig 0.11.0", Compiler::Zig, "0.11.0", &[]);
+}
+
+#[test]
+fn unknown() {
+    let producer = Producer::parse("some in-house compiler -O2");
+    assert_eq!(producer.compiler(), Compiler::Unknown);
+    assert_eq!(producer.version(), None);
+    assert_eq!(producer.flags(), ["-O2"]);
+
+    let producer = Producer::parse("");
+    assert_eq!(producer.compiler(), Compiler::Unknown);
+    assert!(producer.flags().is_empty());
+}
+
+#[test]
+fn versions() {
+    assert_eq!(
+        Version::parse("14.0.0-1ubuntu1.1"),
+        Some(Version {
+            major: 14,
+            minor: 0,
+            patch: 0,
+            pre: Some("1ubuntu1.1".into()),
+        })
+    );
+    assert_eq!(version("5.9").to_string(), "5.9.0");
+    assert_eq!(version("v2.105.0").to_string(), "2.105.0");
+    assert_eq!(version("1.22.0-nightly").to_string(), "1.22.0-nightly");
+    assert_eq!(Version::parse("nightly"), None);
+    assert_eq!(Version::parse(""), None);
+}
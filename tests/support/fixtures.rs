@@ -0,0 +1,166 @@
+//! Fixtures shared between the library tests and the command line tests.
+
+use super::{ar, comment, fat, gnu_property_note, macho, wasm, wasm_producers};
+use super::{Ar, DwarfBuilder, Elf, UnitSpec, Value};
+use gimli;
+use object;
+
+/// Little-endian DWARF with one DWARF 4 compilation unit for each producer.
+pub fn dwarf_with_producers(producers: &[&str]) -> DwarfBuilder {
+    let mut dwarf = DwarfBuilder::new(object::Endianness::Little);
+    for producer in producers {
+        dwarf.unit(&UnitSpec::new(producer));
+    }
+    dwarf
+}
+
+/// A Mach-O object file for the given architecture with a single compilation
+/// unit.
+pub fn macho_with_producer(arch: object::Architecture, producer: &str) -> Vec<u8> {
+    macho(arch, &dwarf_with_producers(&[producer]).sections())
+}
+
+/// A universal binary with the given x86_64 and arm64 slices.
+pub fn universal(x86_64: Vec<u8>, arm64: Vec<u8>) -> Vec<u8> {
+    use object::macho::*;
+    fat(&[
+        (CPU_TYPE_X86_64, CPU_SUBTYPE_X86_64_ALL, x86_64),
+        (CPU_TYPE_ARM64, CPU_SUBTYPE_ARM64_ALL, arm64),
+    ])
+}
+
+/// A universal binary whose x86_64 slice was built by `clang for x86_64`, and
+/// whose arm64 slice was built by `clang for arm64`.
+pub fn universal_binary() -> Vec<u8> {
+    universal(
+        macho_with_producer(object::Architecture::X86_64, "clang for x86_64"),
+        macho_with_producer(object::Architecture::Aarch64, "clang for arm64"),
+    )
+}
+
+/// The `producers` section of a Rust module processed by `wasm-bindgen`.
+pub fn rust_wasm_producers() -> Vec<u8> {
+    wasm_producers(&[
+        ("language", &[("Rust", "")]),
+        (
+            "processed-by",
+            &[
+                ("rustc", "1.75.0 (82e1608df 2023-12-21)"),
+                ("wasm-bindgen", "0.2.89"),
+            ],
+        ),
+    ])
+}
+
+/// A WebAssembly module with a compilation unit with the given producer, and
+/// the `rust_wasm_producers` section.
+pub fn wasm_module(producer: &str) -> Vec<u8> {
+    let mut sections = dwarf_with_producers(&[producer]).sections();
+    sections.push(("producers", rust_wasm_producers()));
+    wasm(&sections)
+}
+
+/// Like `wasm_module`, but with the `producers` section cut short.
+pub fn wasm_module_with_truncated_producers(producer: &str) -> Vec<u8> {
+    let mut producers = rust_wasm_producers();
+    let len = producers.len();
+    producers.truncate(len - 4);
+    let mut sections = dwarf_with_producers(&[producer]).sections();
+    sections.push(("producers", producers));
+    wasm(&sections)
+}
+
+/// The members of `static_library`, and their producers.
+pub const STATIC_LIBRARY_MEMBERS: &[(&str, &str)] = &[
+    ("a.o", "GNU C17 12.2.0 -g"),
+    ("a_rather_long_member_name.o", "clang version 17.0.6"),
+];
+
+/// A static library of the given kind with the `STATIC_LIBRARY_MEMBERS`.
+pub fn static_library(kind: Ar) -> Vec<u8> {
+    let members: Vec<_> = STATIC_LIBRARY_MEMBERS
+        .iter()
+        .map(|&(name, producer)| (name, Elf::new().producers(&[producer]).write()))
+        .collect();
+    ar(kind, &members)
+}
+
+/// A fully stripped binary, with toolchain records in its `.comment` and
+/// `.note.gnu.property` sections.
+pub fn stripped_binary() -> Vec<u8> {
+    use object::elf;
+
+    Elf::new()
+        .section(comment(&[
+            "GCC: (GNU) 12.2.0",
+            "rustc version 1.75.0 (82e1608df 2023-12-21)",
+            "GCC: (GNU) 12.2.0",
+            "Linker: LLD 17.0.6",
+        ]))
+        .section(gnu_property_note(&[
+            (
+                elf::GNU_PROPERTY_X86_FEATURE_1_AND,
+                elf::GNU_PROPERTY_X86_FEATURE_1_IBT | elf::GNU_PROPERTY_X86_FEATURE_1_SHSTK,
+            ),
+            (elf::GNU_PROPERTY_X86_ISA_1_NEEDED, elf::GNU_PROPERTY_X86_ISA_1_BASELINE),
+        ]))
+        .write()
+}
+
+/// An object file with DWARF 4 and DWARF 5 compilation units, and a type
+/// unit of each version, and the `.debug_info` offset of the DWARF 5 type unit.
+pub fn type_units_object() -> (Vec<u8>, u64) {
+    let type_unit = |version, type_signature, producer: &str| {
+        UnitSpec::type_unit(version, type_signature)
+            .attr(gimli::DW_AT_producer, Value::Strp(producer.into()))
+            .attr(gimli::DW_AT_language, Value::Data2(gimli::DW_LANG_C_plus_plus_14.0))
+            .child(
+                gimli::DW_TAG_structure_type,
+                vec![(gimli::DW_AT_name, Value::Strp("Widget".into()))],
+            )
+    };
+
+    let mut dwarf = DwarfBuilder::new(object::Endianness::Little);
+    dwarf.unit(&UnitSpec::new("clang version 17.0.6 -gdwarf-4"));
+    dwarf.unit(&type_unit(5, 0x1234, "clang version 17.0.6 -gdwarf-5 -fdebug-types-section"));
+    // A DWARF 5 type unit header is 24 bytes.
+    let type_unit_offset = dwarf.last_root_offset() - 24;
+    dwarf.unit(
+        &UnitSpec::version(5)
+            .attr(gimli::DW_AT_producer, Value::Strp("clang version 17.0.6 -gdwarf-5".into())),
+    );
+    dwarf.unit(&type_unit(4, 0x5678, "clang version 17.0.6 -gdwarf-4 -fdebug-types-section"));
+    (Elf::new().dwarf(&dwarf).write(), type_unit_offset)
+}
+
+/// An object file whose `.debug_info` has a unit with a corrupt header, a unit
+/// that refers to the missing `.debug_str` section, and a truncated unit at the
+/// end, among units that can be read, and the offsets of the broken units.
+pub fn damaged_object() -> (Vec<u8>, Vec<u64>) {
+    let unit = |producer: &str| {
+        UnitSpec::version(4).attr(gimli::DW_AT_producer, Value::String(producer.into()))
+    };
+    let mut dwarf = DwarfBuilder::new(object::Endianness::Little);
+    let mut broken = vec![];
+    dwarf.unit(&unit("GNU C17 13.2.0 -g"));
+    dwarf.unit(&UnitSpec {
+        version: 1,
+        ..unit("GNU C 2.95")
+    });
+    broken.push(dwarf.last_unit_offset());
+    dwarf.unit(&unit("clang version 17.0.6"));
+    dwarf.unit(&UnitSpec::new("rustc version 1.75.0"));
+    broken.push(dwarf.last_unit_offset());
+    dwarf.unit(&unit("GNU C17 13.2.0 -O2"));
+
+    let mut sections: Vec<_> = dwarf
+        .sections()
+        .into_iter()
+        .filter(|&(name, _)| name != ".debug_str")
+        .collect();
+    // A DWARF 4 unit that claims to be longer than the rest of the section.
+    let debug_info = &mut sections[0].1;
+    broken.push(debug_info.len() as u64);
+    debug_info.extend(&[0x00, 0x01, 0x00, 0x00, 0x04, 0x00]);
+    (Elf::new().debug_sections(sections).write(), broken)
+}
@@ -0,0 +1,1133 @@
+//! Helpers for synthesizing object files containing DWARF, so that tests can
+//! exercise targets, encodings and forms that the host toolchain doesn't
+//! produce.
+
+#![allow(dead_code)]
+
+pub mod fixtures;
+
+use crc32fast;
+use flate2;
+use flate2::write::ZlibEncoder;
+use gimli;
+use object;
+use object::write;
+use ruzstd;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+
+/// An attribute value, along with the form it should be encoded with.
+#[derive(Clone, Debug)]
+pub enum Value {
+    /// `DW_FORM_strp` into `.debug_str`.
+    Strp(String),
+    /// `DW_FORM_string`, stored inline in the entry.
+    String(String),
+    /// One of the `DW_FORM_strx*` forms, indexing `.debug_str_offsets`.
+    Strx(gimli::DwForm, String),
+    /// One of the `DW_FORM_addrx*` forms or `DW_FORM_GNU_addr_index`,
+    /// indexing `.debug_addr`.
+    Addrx(gimli::DwForm, u64),
+    /// `DW_FORM_line_strp` into `.debug_line_str`.
+    LineStrp(String),
+    /// `DW_FORM_block1`.
+    Block(Vec<u8>),
+    /// `DW_FORM_addr`, which is the size of the unit's addresses.
+    Addr(u64),
+    /// `DW_FORM_data1`.
+    Data1(u8),
+    /// `DW_FORM_data2`.
+    Data2(u16),
+    /// `DW_FORM_data8`.
+    Data8(u64),
+    /// `DW_FORM_sec_offset`.
+    SecOffset(u64),
+    /// `DW_FORM_ref_addr`, an offset into `.debug_info`.
+    RefAddr(u64),
+    /// `DW_FORM_GNU_ref_alt`, an offset into the supplementary file's
+    /// `.debug_info`.
+    GnuRefAlt(u64),
+    /// `DW_FORM_GNU_strp_alt`, an offset into the supplementary file's
+    /// `.debug_str`.
+    GnuStrpAlt(u64),
+}
+
+/// A description of a single unit: its root entry, and optionally some
+/// childless children of the root.
+#[derive(Clone, Debug)]
+pub struct UnitSpec {
+    pub version: u16,
+    pub dwarf64: bool,
+    pub address_size: u8,
+    pub unit_type: gimli::DwUt,
+    /// The unit ID in DWARF 5 skeleton and split unit headers.
+    pub dwo_id: Option<u64>,
+    /// The type signature in type unit headers.
+    pub type_signature: Option<u64>,
+    pub tag: gimli::DwTag,
+    pub attrs: Vec<(gimli::DwAt, Value)>,
+    pub children: Vec<(gimli::DwTag, Vec<(gimli::DwAt, Value)>)>,
+}
+
+impl UnitSpec {
+    /// A DWARF 4 compilation unit with the given producer stored via
+    /// `DW_FORM_strp`.
+    pub fn new(producer: &str) -> UnitSpec {
+        UnitSpec::version(4).attr(gimli::DW_AT_producer, Value::Strp(producer.into()))
+    }
+
+    /// A compilation unit without any attributes, using the given DWARF
+    /// version.
+    pub fn version(version: u16) -> UnitSpec {
+        UnitSpec {
+            version,
+            dwarf64: false,
+            address_size: 8,
+            unit_type: gimli::DW_UT_compile,
+            dwo_id: None,
+            type_signature: None,
+            tag: gimli::DW_TAG_compile_unit,
+            attrs: vec![],
+            children: vec![],
+        }
+    }
+
+    /// A DWARF 5 unit of the given type with the given unit ID in its header,
+    /// such as a skeleton unit or a split compilation unit.
+    pub fn split(unit_type: gimli::DwUt, dwo_id: u64) -> UnitSpec {
+        let tag = if unit_type == gimli::DW_UT_skeleton {
+            gimli::DW_TAG_skeleton_unit
+        } else {
+            gimli::DW_TAG_compile_unit
+        };
+        UnitSpec {
+            unit_type,
+            dwo_id: Some(dwo_id),
+            tag,
+            ..UnitSpec::version(5)
+        }
+    }
+
+    /// A type unit with the given type signature, using the given DWARF
+    /// version. DWARF 4 type units go in `.debug_types`, and DWARF 5 ones in
+    /// `.debug_info`. The type itself should be added as the first child.
+    pub fn type_unit(version: u16, type_signature: u64) -> UnitSpec {
+        UnitSpec {
+            unit_type: gimli::DW_UT_type,
+            type_signature: Some(type_signature),
+            tag: gimli::DW_TAG_type_unit,
+            ..UnitSpec::version(version)
+        }
+    }
+
+    /// Add an attribute to the unit's root entry.
+    pub fn attr(mut self, name: gimli::DwAt, value: Value) -> UnitSpec {
+        self.attrs.push((name, value));
+        self
+    }
+
+    /// Add a child to the unit's root entry.
+    pub fn child(mut self, tag: gimli::DwTag, attrs: Vec<(gimli::DwAt, Value)>) -> UnitSpec {
+        self.children.push((tag, attrs));
+        self
+    }
+}
+
+/// Where a unit's contributions to each section live, as `(offset, size)`.
+#[derive(Clone, Copy, Debug)]
+struct UnitLayout {
+    dwo_id: Option<u64>,
+    root: u64,
+    info: (u64, u64),
+    abbrev: (u64, u64),
+    str_offsets: (u64, u64),
+}
+
+impl UnitLayout {
+    fn column(&self, section: gimli::DwSect) -> (u64, u64) {
+        match section {
+            gimli::DW_SECT_INFO => self.info,
+            gimli::DW_SECT_ABBREV => self.abbrev,
+            gimli::DW_SECT_STR_OFFSETS => self.str_offsets,
+            _ => panic!("no contribution to {}", section),
+        }
+    }
+}
+
+/// A reference from `.debug_info` into another section, which the assembler
+/// would leave to the linker to relocate.
+#[derive(Clone, Debug)]
+pub struct Relocation {
+    /// Where the reference is within `.debug_info`.
+    pub offset: u64,
+    /// The section it refers to.
+    pub section: &'static str,
+    /// The offset it refers to within that section.
+    pub addend: u64,
+    /// The size of the reference, in bytes.
+    pub size: usize,
+}
+
+/// Hand-assembles DWARF sections. Unlike `gimli::write`, this gives full
+/// control over forms and unit headers.
+#[derive(Debug)]
+pub struct DwarfBuilder {
+    big_endian: bool,
+    package: bool,
+    debug_info: Vec<u8>,
+    debug_types: Vec<u8>,
+    debug_abbrev: Vec<u8>,
+    debug_str: Vec<u8>,
+    debug_line_str: Vec<u8>,
+    debug_str_offsets: Vec<u8>,
+    debug_addr: Vec<u8>,
+    layouts: Vec<UnitLayout>,
+    relocations: Vec<Relocation>,
+}
+
+impl DwarfBuilder {
+    pub fn new(endian: object::Endianness) -> DwarfBuilder {
+        DwarfBuilder {
+            big_endian: endian == object::Endianness::Big,
+            package: false,
+            debug_info: vec![],
+            debug_types: vec![],
+            debug_abbrev: vec![],
+            debug_str: vec![],
+            debug_line_str: vec![],
+            debug_str_offsets: vec![],
+            debug_addr: vec![],
+            layouts: vec![],
+            relocations: vec![],
+        }
+    }
+
+    /// A builder for the units of a `.dwp` package, whose unit headers use
+    /// abbreviation offsets relative to the unit's contribution.
+    pub fn package(endian: object::Endianness) -> DwarfBuilder {
+        DwarfBuilder {
+            package: true,
+            ..DwarfBuilder::new(endian)
+        }
+    }
+
+    /// Append a unit to `.debug_info`, or to `.debug_types` for DWARF 4 type
+    /// units.
+    pub fn unit(&mut self, unit: &UnitSpec) -> &mut DwarfBuilder {
+        let offset_size = if unit.dwarf64 { 8 } else { 4 };
+
+        // Lay out this unit's `.debug_str_offsets` contribution first, so
+        // that entries can refer to its base.
+        let strx: Vec<&str> = unit.attrs
+            .iter()
+            .chain(unit.children.iter().flat_map(|(_, attrs)| attrs))
+            .filter_map(|(_, value)| match *value {
+                Value::Strx(_, ref s) => Some(s.as_str()),
+                _ => None,
+            })
+            .collect();
+        let mut attrs = unit.attrs.clone();
+        let str_offsets_start = self.debug_str_offsets.len() as u64;
+        if !strx.is_empty() {
+            let mut contribution = vec![];
+            for s in &strx {
+                let offset = push_str(&mut self.debug_str, s);
+                self.offset(&mut contribution, offset, offset_size);
+            }
+            let mut header = vec![];
+            self.initial_length(&mut header, contribution.len() as u64 + 4, unit.dwarf64);
+            self.uint(&mut header, 5, 2);
+            self.uint(&mut header, 0, 2);
+            let base = (self.debug_str_offsets.len() + header.len()) as u64;
+            self.debug_str_offsets.extend(header);
+            self.debug_str_offsets.extend(contribution);
+            // Split units have an implicit base, just after the header.
+            let split = unit.unit_type == gimli::DW_UT_split_compile
+                || unit.unit_type == gimli::DW_UT_split_type;
+            if !split {
+                attrs.push((gimli::DW_AT_str_offsets_base, Value::SecOffset(base)));
+            }
+        }
+        let str_offsets_end = self.debug_str_offsets.len() as u64;
+
+        // Likewise for the `.debug_addr` contribution, which has a header in
+        // DWARF 5 but not in the GNU extension to DWARF 4.
+        let addrx: Vec<(gimli::DwForm, u64)> = unit.attrs
+            .iter()
+            .chain(unit.children.iter().flat_map(|(_, attrs)| attrs))
+            .filter_map(|(_, value)| match *value {
+                Value::Addrx(form, address) => Some((form, address)),
+                _ => None,
+            })
+            .collect();
+        if let Some(&(form, _)) = addrx.first() {
+            let address_size = usize::from(unit.address_size);
+            let mut contribution = vec![];
+            for &(_, address) in &addrx {
+                self.uint(&mut contribution, address, address_size);
+            }
+            let gnu = form == gimli::DW_FORM_GNU_addr_index;
+            if !gnu {
+                let mut header = vec![];
+                self.initial_length(&mut header, contribution.len() as u64 + 4, unit.dwarf64);
+                self.uint(&mut header, 5, 2);
+                header.push(unit.address_size);
+                header.push(0);
+                self.debug_addr.extend(header);
+            }
+            let base = self.debug_addr.len() as u64;
+            self.debug_addr.extend(contribution);
+            let name = if gnu {
+                gimli::DW_AT_GNU_addr_base
+            } else {
+                gimli::DW_AT_addr_base
+            };
+            attrs.push((name, Value::SecOffset(base)));
+        }
+
+        // One abbreviation for the root entry, and one for each child.
+        let abbrev_offset = self.debug_abbrev.len() as u64;
+        let has_children = !unit.children.is_empty();
+        self.abbrev(1, unit.tag, has_children, &attrs);
+        for (i, &(tag, ref child_attrs)) in unit.children.iter().enumerate() {
+            self.abbrev(i as u64 + 2, tag, false, child_attrs);
+        }
+        self.debug_abbrev.push(0);
+
+        let mut entries = vec![];
+        let mut indexes = (0, 0);
+        let mut relocations = vec![];
+        uleb(&mut entries, 1);
+        let sizes = (offset_size, unit.address_size as usize);
+        self.attrs(&mut entries, &attrs, sizes, &mut indexes, &mut relocations);
+        let root_size = entries.len();
+        if has_children {
+            for (i, (_, child_attrs)) in unit.children.iter().enumerate() {
+                uleb(&mut entries, i as u64 + 2);
+                self.attrs(&mut entries, child_attrs, sizes, &mut indexes, &mut relocations);
+            }
+            entries.push(0);
+        }
+
+        let header_abbrev_offset = if self.package { 0 } else { abbrev_offset };
+        let mut header = vec![];
+        self.uint(&mut header, u64::from(unit.version), 2);
+        let abbrev_reference = if unit.version >= 5 { 4 } else { 2 };
+        if unit.version >= 5 {
+            header.push(unit.unit_type.0);
+            header.push(unit.address_size);
+            self.offset(&mut header, header_abbrev_offset, offset_size);
+            if let Some(dwo_id) = unit.dwo_id {
+                self.uint(&mut header, dwo_id, 8);
+            }
+        } else {
+            self.offset(&mut header, header_abbrev_offset, offset_size);
+            header.push(unit.address_size);
+        }
+        if let Some(type_signature) = unit.type_signature {
+            // The type is the root's first child, if it has any, and is
+            // referred to relative to the start of the unit.
+            self.uint(&mut header, type_signature, 8);
+            let initial_length_size = if unit.dwarf64 { 12 } else { 4 };
+            let mut type_offset = initial_length_size + header.len() + offset_size;
+            if has_children {
+                type_offset += root_size;
+            }
+            self.offset(&mut header, type_offset as u64, offset_size);
+        }
+
+        let length = (header.len() + entries.len()) as u64;
+        let mut info = vec![];
+        self.initial_length(&mut info, length, unit.dwarf64);
+
+        // DWARF 4 type units have a section of their own, and nothing refers
+        // to them by offset.
+        if unit.version < 5 && unit.type_signature.is_some() {
+            self.debug_types.extend(info);
+            self.debug_types.extend(header);
+            self.debug_types.extend(entries);
+            return self;
+        }
+        let info_start = self.debug_info.len() as u64;
+        self.debug_info.extend(info);
+        self.debug_info.extend(&header);
+        let root = self.debug_info.len() as u64;
+        self.debug_info.extend(entries);
+
+        if !self.package {
+            self.relocations.push(Relocation {
+                offset: root - header.len() as u64 + abbrev_reference,
+                section: ".debug_abbrev",
+                addend: abbrev_offset,
+                size: offset_size,
+            });
+        }
+        for mut relocation in relocations {
+            relocation.offset += root;
+            self.relocations.push(relocation);
+        }
+
+        self.layouts.push(UnitLayout {
+            dwo_id: unit.dwo_id,
+            root,
+            info: (info_start, self.debug_info.len() as u64 - info_start),
+            abbrev: (abbrev_offset, self.debug_abbrev.len() as u64 - abbrev_offset),
+            str_offsets: (str_offsets_start, str_offsets_end - str_offsets_start),
+        });
+        self
+    }
+
+    /// The `.debug_info` offset of the most recently appended unit's header.
+    pub fn last_unit_offset(&self) -> u64 {
+        self.layouts.last().expect("should have a unit").info.0
+    }
+
+    /// The `.debug_info` offset of the root entry of the most recently
+    /// appended unit, for use with `Value::RefAddr` and friends.
+    pub fn last_root_offset(&self) -> u64 {
+        self.layouts.last().expect("should have a unit").root
+    }
+
+    /// Append a string to `.debug_str` and return its offset, for use with
+    /// `Value::GnuStrpAlt` in files referring to this one.
+    pub fn string(&mut self, s: &str) -> u64 {
+        push_str(&mut self.debug_str, s)
+    }
+
+    fn abbrev(
+        &mut self,
+        code: u64,
+        tag: gimli::DwTag,
+        children: bool,
+        attrs: &[(gimli::DwAt, Value)],
+    ) {
+        uleb(&mut self.debug_abbrev, code);
+        uleb(&mut self.debug_abbrev, u64::from(tag.0));
+        self.debug_abbrev.push(if children {
+            gimli::DW_CHILDREN_yes.0
+        } else {
+            gimli::DW_CHILDREN_no.0
+        });
+        for &(name, ref value) in attrs {
+            uleb(&mut self.debug_abbrev, u64::from(name.0));
+            uleb(&mut self.debug_abbrev, u64::from(form(value).0));
+        }
+        self.debug_abbrev.extend(&[0, 0]);
+    }
+
+    /// Encode the values of the given attributes, with `sizes` being the
+    /// unit's offset size and address size, and `indexes` the next index into
+    /// the unit's `.debug_str_offsets` and `.debug_addr` contributions.
+    fn attrs(
+        &mut self,
+        entry: &mut Vec<u8>,
+        attrs: &[(gimli::DwAt, Value)],
+        sizes: (usize, usize),
+        indexes: &mut (u64, u64),
+        relocations: &mut Vec<Relocation>,
+    ) {
+        let (offset_size, address_size) = sizes;
+        for (_, value) in attrs {
+            match *value {
+                Value::Strp(ref s) => {
+                    let offset = push_str(&mut self.debug_str, s);
+                    relocations.push(Relocation {
+                        offset: entry.len() as u64,
+                        section: ".debug_str",
+                        addend: offset,
+                        size: offset_size,
+                    });
+                    self.offset(entry, offset, offset_size);
+                }
+                Value::String(ref s) => {
+                    push_str(entry, s);
+                }
+                Value::Strx(form, _) => {
+                    self.index(entry, form, indexes.0);
+                    indexes.0 += 1;
+                }
+                Value::Addrx(form, _) => {
+                    self.index(entry, form, indexes.1);
+                    indexes.1 += 1;
+                }
+                Value::LineStrp(ref s) => {
+                    let offset = push_str(&mut self.debug_line_str, s);
+                    relocations.push(Relocation {
+                        offset: entry.len() as u64,
+                        section: ".debug_line_str",
+                        addend: offset,
+                        size: offset_size,
+                    });
+                    self.offset(entry, offset, offset_size);
+                }
+                Value::Block(ref data) => {
+                    entry.push(data.len() as u8);
+                    entry.extend(data);
+                }
+                Value::Addr(address) => self.uint(entry, address, address_size),
+                Value::Data1(value) => entry.push(value),
+                Value::Data2(value) => self.uint(entry, u64::from(value), 2),
+                Value::Data8(value) => self.uint(entry, value, 8),
+                Value::SecOffset(offset)
+                | Value::RefAddr(offset)
+                | Value::GnuRefAlt(offset)
+                | Value::GnuStrpAlt(offset) => self.offset(entry, offset, offset_size),
+            }
+        }
+    }
+
+    /// Encode an index with one of the `strx` or `addrx` forms.
+    fn index(&self, entry: &mut Vec<u8>, form: gimli::DwForm, index: u64) {
+        let size = match form {
+            gimli::DW_FORM_strx1 | gimli::DW_FORM_addrx1 => 1,
+            gimli::DW_FORM_strx2 | gimli::DW_FORM_addrx2 => 2,
+            gimli::DW_FORM_strx3 | gimli::DW_FORM_addrx3 => 3,
+            gimli::DW_FORM_strx4 | gimli::DW_FORM_addrx4 => 4,
+            _ => 0,
+        };
+        if size == 0 {
+            uleb(entry, index);
+        } else {
+            self.uint(entry, index, size);
+        }
+    }
+
+    /// The references from `.debug_info` into other sections, with their
+    /// offsets relative to the start of `.debug_info`. Packages have none.
+    pub fn relocations(&self) -> &[Relocation] {
+        &self.relocations
+    }
+
+    /// The non-empty assembled sections, keyed by their ELF names.
+    pub fn sections(&self) -> Vec<(&'static str, Vec<u8>)> {
+        vec![
+            (".debug_info", self.debug_info.clone()),
+            (".debug_types", self.debug_types.clone()),
+            (".debug_abbrev", self.debug_abbrev.clone()),
+            (".debug_str", self.debug_str.clone()),
+            (".debug_line_str", self.debug_line_str.clone()),
+            (".debug_str_offsets", self.debug_str_offsets.clone()),
+            (".debug_addr", self.debug_addr.clone()),
+        ].into_iter()
+            .filter(|(_, data)| !data.is_empty())
+            .collect()
+    }
+
+    /// The non-empty assembled sections, with the names they have in a `.dwo`
+    /// file.
+    pub fn dwo_sections(&self) -> Vec<(&'static str, Vec<u8>)> {
+        self.sections()
+            .into_iter()
+            .map(|(name, data)| {
+                let name = match name {
+                    ".debug_info" => ".debug_info.dwo",
+                    ".debug_types" => ".debug_types.dwo",
+                    ".debug_abbrev" => ".debug_abbrev.dwo",
+                    ".debug_str" => ".debug_str.dwo",
+                    ".debug_line_str" => ".debug_line_str.dwo",
+                    ".debug_str_offsets" => ".debug_str_offsets.dwo",
+                    name => panic!("no .dwo name for {}", name),
+                };
+                (name, data)
+            })
+            .collect()
+    }
+
+    /// The assembled sections as a DWARF 5 `.dwp` package, with a
+    /// `.debug_cu_index` for every unit that has a unit ID. The builder should
+    /// have been created with `DwarfBuilder::package`.
+    pub fn dwp_sections(&self) -> Vec<(&'static str, Vec<u8>)> {
+        let units: Vec<_> = self.layouts.iter().filter(|l| l.dwo_id.is_some()).collect();
+        let slot_count = (units.len() + 1).next_power_of_two() * 2;
+        let mask = slot_count as u64 - 1;
+
+        // Open addressing, as in the DWARF 5 specification.
+        let mut ids = vec![0; slot_count];
+        let mut rows = vec![0; slot_count];
+        for (row, layout) in units.iter().enumerate() {
+            let id = layout.dwo_id.unwrap();
+            let step = ((id >> 32) & mask) | 1;
+            let mut slot = id & mask;
+            while rows[slot as usize] != 0 {
+                slot = (slot + step) & mask;
+            }
+            ids[slot as usize] = id;
+            rows[slot as usize] = row as u64 + 1;
+        }
+
+        let columns = [
+            gimli::DW_SECT_INFO,
+            gimli::DW_SECT_ABBREV,
+            gimli::DW_SECT_STR_OFFSETS,
+        ];
+
+        let mut index = vec![];
+        self.uint(&mut index, 5, 2);
+        self.uint(&mut index, 0, 2);
+        self.uint(&mut index, columns.len() as u64, 4);
+        self.uint(&mut index, units.len() as u64, 4);
+        self.uint(&mut index, slot_count as u64, 4);
+        for id in ids {
+            self.uint(&mut index, id, 8);
+        }
+        for row in rows {
+            self.uint(&mut index, row, 4);
+        }
+        for section in &columns {
+            self.uint(&mut index, u64::from(section.0), 4);
+        }
+        for layout in &units {
+            for &section in &columns {
+                self.uint(&mut index, layout.column(section).0, 4);
+            }
+        }
+        for layout in &units {
+            for &section in &columns {
+                self.uint(&mut index, layout.column(section).1, 4);
+            }
+        }
+
+        let mut sections = self.dwo_sections();
+        sections.push((".debug_cu_index", index));
+        sections
+    }
+
+    fn uint(&self, out: &mut Vec<u8>, value: u64, size: usize) {
+        let bytes = if self.big_endian {
+            value.to_be_bytes()[8 - size..].to_vec()
+        } else {
+            value.to_le_bytes()[..size].to_vec()
+        };
+        out.extend(bytes);
+    }
+
+    fn offset(&self, out: &mut Vec<u8>, offset: u64, offset_size: usize) {
+        self.uint(out, offset, offset_size);
+    }
+
+    fn initial_length(&self, out: &mut Vec<u8>, length: u64, dwarf64: bool) {
+        if dwarf64 {
+            self.uint(out, 0xffff_ffff, 4);
+            self.uint(out, length, 8);
+        } else {
+            self.uint(out, length, 4);
+        }
+    }
+}
+
+fn form(value: &Value) -> gimli::DwForm {
+    match *value {
+        Value::Strp(_) => gimli::DW_FORM_strp,
+        Value::String(_) => gimli::DW_FORM_string,
+        Value::Strx(form, _) | Value::Addrx(form, _) => form,
+        Value::LineStrp(_) => gimli::DW_FORM_line_strp,
+        Value::Block(_) => gimli::DW_FORM_block1,
+        Value::Addr(_) => gimli::DW_FORM_addr,
+        Value::Data1(_) => gimli::DW_FORM_data1,
+        Value::Data2(_) => gimli::DW_FORM_data2,
+        Value::Data8(_) => gimli::DW_FORM_data8,
+        Value::SecOffset(_) => gimli::DW_FORM_sec_offset,
+        Value::RefAddr(_) => gimli::DW_FORM_ref_addr,
+        Value::GnuRefAlt(_) => gimli::DW_FORM_GNU_ref_alt,
+        Value::GnuStrpAlt(_) => gimli::DW_FORM_GNU_strp_alt,
+    }
+}
+
+/// Append a NUL-terminated string and return the offset it was written at.
+fn push_str(out: &mut Vec<u8>, s: &str) -> u64 {
+    let offset = out.len() as u64;
+    out.extend(s.as_bytes());
+    out.push(0);
+    offset
+}
+
+fn uleb(out: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+/// Builds ELF relocatable objects, which are for little-endian x86_64 unless
+/// built with `Elf::target`.
+#[derive(Clone, Debug)]
+pub struct Elf {
+    arch: object::Architecture,
+    endian: object::Endianness,
+    sections: Vec<(String, object::SectionKind, Vec<u8>)>,
+    relocations: Vec<Relocation>,
+    compression: Option<Compression>,
+}
+
+impl Elf {
+    pub fn new() -> Elf {
+        Elf::target(object::Architecture::X86_64, object::Endianness::Little)
+    }
+
+    /// An object for the given architecture and endianness.
+    pub fn target(arch: object::Architecture, endian: object::Endianness) -> Elf {
+        Elf {
+            arch,
+            endian,
+            sections: vec![],
+            relocations: vec![],
+            compression: None,
+        }
+    }
+
+    /// Add a section, such as one made by `build_id_note`.
+    pub fn section(mut self, (name, kind, data): (&str, object::SectionKind, Vec<u8>)) -> Elf {
+        self.sections.push((name.into(), kind, data));
+        self
+    }
+
+    /// Add the given debug sections.
+    pub fn debug_sections(mut self, sections: Vec<(&str, Vec<u8>)>) -> Elf {
+        for (name, data) in sections {
+            self = self.section((name, object::SectionKind::Debug, data));
+        }
+        self
+    }
+
+    /// Add the sections of the given DWARF, as a linker would leave them.
+    pub fn dwarf(self, dwarf: &DwarfBuilder) -> Elf {
+        self.debug_sections(dwarf.sections())
+    }
+
+    /// Add the sections of the given DWARF unlinked, like the assembler writes
+    /// them, in which `.debug_info`'s references into other sections are
+    /// zero, with a relocation against the other section for each.
+    pub fn unlinked_dwarf(mut self, dwarf: &DwarfBuilder) -> Elf {
+        let mut sections = dwarf.sections();
+        for &mut (name, ref mut data) in &mut sections {
+            if name != ".debug_info" {
+                continue;
+            }
+            for relocation in dwarf.relocations() {
+                let start = relocation.offset as usize;
+                data[start..start + relocation.size].iter_mut().for_each(|b| *b = 0);
+            }
+        }
+        self.relocations.extend(dwarf.relocations().iter().cloned());
+        self.debug_sections(sections)
+    }
+
+    /// Add DWARF with one DWARF 4 compilation unit for each producer.
+    pub fn producers(self, producers: &[&str]) -> Elf {
+        let mut dwarf = DwarfBuilder::new(self.endian);
+        for producer in producers {
+            dwarf.unit(&UnitSpec::new(producer));
+        }
+        self.dwarf(&dwarf)
+    }
+
+    /// Leave out the section with the given name, such as `.debug_str` to
+    /// make the units that refer to it unreadable.
+    pub fn without(mut self, name: &str) -> Elf {
+        self.sections.retain(|(section, _, _)| section != name);
+        self
+    }
+
+    /// Compress the debug sections in the given way. Only for little-endian
+    /// 64-bit targets.
+    pub fn compressed(mut self, compression: Compression) -> Elf {
+        self.compression = Some(compression);
+        self
+    }
+
+    pub fn write(&self) -> Vec<u8> {
+        let mut obj = write::Object::new(object::BinaryFormat::Elf, self.arch, self.endian);
+        let mut ids = vec![];
+        for &(ref name, kind, ref data) in &self.sections {
+            let (name, data, compressed) = match self.compression {
+                Some(compression) if kind == object::SectionKind::Debug => {
+                    let (name, data) = compress(name, data, compression);
+                    (name, data, compression != Compression::GnuZlib)
+                }
+                _ => (name.clone(), data.clone(), false),
+            };
+            let section = obj.add_section(vec![], name.as_bytes().to_vec(), kind);
+            if compressed {
+                obj.section_mut(section).flags = object::SectionFlags::Elf {
+                    sh_flags: u64::from(object::elf::SHF_COMPRESSED),
+                };
+            }
+            let align = if kind == object::SectionKind::Note { 4 } else { 1 };
+            obj.append_section_data(section, &data, align);
+            ids.push((name, section));
+        }
+
+        let id = |name: &str| ids.iter().find(|(n, _)| n == name).expect("should have section").1;
+        for relocation in &self.relocations {
+            let symbol = obj.section_symbol(id(relocation.section));
+            obj.add_relocation(
+                id(".debug_info"),
+                write::Relocation {
+                    offset: relocation.offset,
+                    symbol,
+                    addend: relocation.addend as i64,
+                    flags: object::RelocationFlags::Generic {
+                        kind: object::RelocationKind::Absolute,
+                        encoding: object::RelocationEncoding::Generic,
+                        size: relocation.size as u8 * 8,
+                    },
+                },
+            )
+            .expect("should add relocation");
+        }
+        obj.write().expect("should write object file")
+    }
+}
+
+/// Compress the given debug section in the given way, returning its new name
+/// and data.
+fn compress(name: &str, data: &[u8], compression: Compression) -> (String, Vec<u8>) {
+    let (ch_type, payload) = match compression {
+        Compression::GnuZlib => return gnu_zlib(name, data),
+        Compression::Zlib => (object::elf::ELFCOMPRESS_ZLIB, zlib(data)),
+        Compression::Zstd => {
+            let level = ruzstd::encoding::CompressionLevel::Fastest;
+            (
+                object::elf::ELFCOMPRESS_ZSTD,
+                ruzstd::encoding::compress_to_vec(data, level),
+            )
+        }
+    };
+    // An `Elf64_Chdr`.
+    let mut compressed = vec![];
+    compressed.extend(&ch_type.to_le_bytes());
+    compressed.extend(&0u32.to_le_bytes());
+    compressed.extend(&(data.len() as u64).to_le_bytes());
+    compressed.extend(&1u64.to_le_bytes());
+    compressed.extend(payload);
+    (name.to_string(), compressed)
+}
+
+/// A little-endian `.note.gnu.build-id` section with the given build ID.
+pub fn build_id_note(build_id: &[u8]) -> (&'static str, object::SectionKind, Vec<u8>) {
+    let mut note = vec![];
+    note.extend(&4u32.to_le_bytes());
+    note.extend(&(build_id.len() as u32).to_le_bytes());
+    note.extend(&object::elf::NT_GNU_BUILD_ID.to_le_bytes());
+    note.extend(b"GNU\0");
+    note.extend(build_id);
+    let padded = (note.len() + 3) & !3;
+    note.resize(padded, 0);
+    (".note.gnu.build-id", object::SectionKind::Note, note)
+}
+
+/// A little-endian `.note.gnu.property` section for a 64-bit file, with the
+/// given `(pr_type, value)` properties, whose values are 4 bytes each.
+pub fn gnu_property_note(properties: &[(u32, u32)]) -> (&'static str, object::SectionKind, Vec<u8>) {
+    let mut desc: Vec<u8> = vec![];
+    for &(pr_type, value) in properties {
+        desc.extend(&pr_type.to_le_bytes());
+        desc.extend(&4u32.to_le_bytes());
+        desc.extend(&value.to_le_bytes());
+        // 64-bit files pad each property to 8 bytes.
+        desc.extend(&[0; 4]);
+    }
+
+    let mut note = vec![];
+    note.extend(&4u32.to_le_bytes());
+    note.extend(&(desc.len() as u32).to_le_bytes());
+    note.extend(&object::elf::NT_GNU_PROPERTY_TYPE_0.to_le_bytes());
+    note.extend(b"GNU\0");
+    note.extend(desc);
+    (".note.gnu.property", object::SectionKind::Note, note)
+}
+
+/// A `.comment` section with the given strings, as linkers write it.
+pub fn comment(strings: &[&str]) -> (&'static str, object::SectionKind, Vec<u8>) {
+    let mut comment = vec![0];
+    for string in strings {
+        push_str(&mut comment, string);
+    }
+    (".comment", object::SectionKind::OtherString, comment)
+}
+
+/// A little-endian `.gnu_debuglink` section naming the given file and
+/// recording the CRC-32 of its contents.
+pub fn debuglink(name: &str, debug_file: &[u8]) -> (&'static str, object::SectionKind, Vec<u8>) {
+    let mut link = name.as_bytes().to_vec();
+    link.push(0);
+    let padded = (link.len() + 3) & !3;
+    link.resize(padded, 0);
+    link.extend(&crc32fast::hash(debug_file).to_le_bytes());
+    (".gnu_debuglink", object::SectionKind::Other, link)
+}
+
+/// A `.gnu_debugaltlink` section naming the given supplementary file and
+/// recording its build ID.
+pub fn debugaltlink(name: &str, build_id: &[u8]) -> (&'static str, object::SectionKind, Vec<u8>) {
+    let mut link = name.as_bytes().to_vec();
+    link.push(0);
+    link.extend(build_id);
+    (".gnu_debugaltlink", object::SectionKind::Other, link)
+}
+
+/// How `Elf::compressed` compresses debug sections.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Compression {
+    /// Legacy GNU `.zdebug_*` sections.
+    GnuZlib,
+    /// `SHF_COMPRESSED` sections using `ELFCOMPRESS_ZLIB`.
+    Zlib,
+    /// `SHF_COMPRESSED` sections using `ELFCOMPRESS_ZSTD`.
+    Zstd,
+}
+
+fn zlib(data: &[u8]) -> Vec<u8> {
+    let mut encoder = ZlibEncoder::new(vec![], flate2::Compression::best());
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap()
+}
+
+/// Compress the given `.debug_*` section into a legacy GNU `.zdebug_*` one,
+/// returning its name and data.
+pub fn gnu_zlib(name: &str, data: &[u8]) -> (String, Vec<u8>) {
+    let mut compressed = b"ZLIB".to_vec();
+    compressed.extend(&(data.len() as u64).to_be_bytes());
+    compressed.extend(zlib(data));
+    (name.replacen(".debug_", ".zdebug_", 1), compressed)
+}
+
+/// Make a Mach-O object file with the given ELF-named DWARF sections, which
+/// are renamed to their Mach-O equivalents in the `__DWARF` segment, such as
+/// `__debug_info` for `.debug_info`. Without any sections, it gets a `__text`
+/// section, like a stripped binary.
+pub fn macho(arch: object::Architecture, sections: &[(&str, Vec<u8>)]) -> Vec<u8> {
+    let mut obj = write::Object::new(object::BinaryFormat::MachO, arch, object::Endianness::Little);
+    if sections.is_empty() {
+        let text = obj.add_section(b"__TEXT".to_vec(), b"__text".to_vec(), object::SectionKind::Text);
+        obj.append_section_data(text, &[0xc3], 1);
+    }
+    for &(name, ref data) in sections {
+        let mut name = format!("__{}", &name[1..]).into_bytes();
+        name.truncate(16);
+        let section = obj.add_section(b"__DWARF".to_vec(), name, object::SectionKind::Debug);
+        obj.append_section_data(section, data, 1);
+    }
+    obj.write().expect("should write object file")
+}
+
+/// Make a universal Mach-O binary from the given `(cputype, cpusubtype,
+/// slice)` triples.
+pub fn fat(slices: &[(u32, u32, Vec<u8>)]) -> Vec<u8> {
+    const ALIGN: usize = 1 << 12;
+
+    let mut out = vec![];
+    out.extend(&object::macho::FAT_MAGIC.to_be_bytes());
+    out.extend(&(slices.len() as u32).to_be_bytes());
+
+    let mut offset = ALIGN;
+    for &(cputype, cpusubtype, ref data) in slices {
+        out.extend(&cputype.to_be_bytes());
+        out.extend(&cpusubtype.to_be_bytes());
+        out.extend(&(offset as u32).to_be_bytes());
+        out.extend(&(data.len() as u32).to_be_bytes());
+        out.extend(&12u32.to_be_bytes());
+        offset = (offset + data.len() + ALIGN - 1) & !(ALIGN - 1);
+    }
+
+    for (_, _, data) in slices {
+        let start = (out.len() + ALIGN - 1) & !(ALIGN - 1);
+        out.resize(start, 0);
+        out.extend(data);
+    }
+    out
+}
+
+/// Make a 64-bit PE image, like MinGW's `ld`, with the given sections. As with
+/// COFF object files, names longer than eight bytes go in the string table,
+/// which follows an empty symbol table.
+pub fn pe(sections: &[(&str, Vec<u8>)]) -> Vec<u8> {
+    use object::pe;
+
+    let mut data = vec![];
+    let mut writer = write::pe::Writer::new(true, 0x1000, 0x200, &mut data);
+    writer.reserve_dos_header_and_stub();
+    writer.reserve_nt_headers(pe::IMAGE_NUMBEROF_DIRECTORY_ENTRIES);
+    writer.reserve_section_headers(sections.len() as u16 + 1);
+    let text = writer.reserve_text_section(1);
+
+    let mut strtab = vec![0; 4];
+    let mut ranges = vec![];
+    for &(name, ref section) in sections {
+        let mut short = [0; 8];
+        if name.len() <= 8 {
+            short[..name.len()].copy_from_slice(name.as_bytes());
+        } else {
+            let offset = format!("/{}", strtab.len());
+            short[..offset.len()].copy_from_slice(offset.as_bytes());
+            strtab.extend(name.as_bytes());
+            strtab.push(0);
+        }
+        let characteristics =
+            pe::IMAGE_SCN_CNT_INITIALIZED_DATA | pe::IMAGE_SCN_MEM_READ | pe::IMAGE_SCN_MEM_DISCARDABLE;
+        let len = section.len() as u32;
+        ranges.push(writer.reserve_section(short, characteristics, len, len));
+    }
+    let strtab_len = strtab.len() as u32;
+    strtab[..4].copy_from_slice(&strtab_len.to_le_bytes());
+    let symtab_offset = writer.reserve(strtab_len, 1);
+
+    writer.write_dos_header_and_stub().expect("should write DOS header");
+    writer.write_nt_headers(write::pe::NtHeaders {
+        machine: pe::IMAGE_FILE_MACHINE_AMD64,
+        time_date_stamp: 0,
+        characteristics: pe::IMAGE_FILE_EXECUTABLE_IMAGE | pe::IMAGE_FILE_LARGE_ADDRESS_AWARE,
+        major_linker_version: 2,
+        minor_linker_version: 41,
+        address_of_entry_point: text.virtual_address,
+        image_base: 0x1_4000_0000,
+        major_operating_system_version: 4,
+        minor_operating_system_version: 0,
+        major_image_version: 0,
+        minor_image_version: 0,
+        major_subsystem_version: 5,
+        minor_subsystem_version: 2,
+        subsystem: pe::IMAGE_SUBSYSTEM_WINDOWS_CUI,
+        dll_characteristics: 0,
+        size_of_stack_reserve: 0x20_0000,
+        size_of_stack_commit: 0x1000,
+        size_of_heap_reserve: 0x10_0000,
+        size_of_heap_commit: 0x1000,
+    });
+    writer.write_section_headers();
+    writer.write_section(text.file_offset, &[0xc3]);
+    for ((_, section), range) in sections.iter().zip(&ranges) {
+        writer.write_section(range.file_offset, section);
+    }
+    writer.pad_until(symtab_offset);
+    writer.write(&strtab);
+
+    // The writer doesn't support symbol tables, so point the file header at
+    // our empty one, which is immediately followed by the string table.
+    let file_header = writer.nt_headers_offset() as usize + 4;
+    data[file_header + 8..file_header + 12].copy_from_slice(&symtab_offset.to_le_bytes());
+    data
+}
+
+/// Make a WebAssembly module with an empty type section, followed by custom
+/// sections with the given names and contents.
+pub fn wasm(sections: &[(&str, Vec<u8>)]) -> Vec<u8> {
+    let mut out = b"\0asm".to_vec();
+    out.extend(&1u32.to_le_bytes());
+    out.extend(&[1, 1, 0]);
+    for &(name, ref data) in sections {
+        let mut section = vec![];
+        wasm_name(&mut section, name);
+        section.extend(data);
+        out.push(0);
+        uleb(&mut out, section.len() as u64);
+        out.extend(section);
+    }
+    out
+}
+
+/// Make the contents of a WebAssembly `producers` section with the given
+/// fields, each with its `(name, version)` pairs.
+pub fn wasm_producers(fields: &[(&str, &[(&str, &str)])]) -> Vec<u8> {
+    let mut out = vec![];
+    uleb(&mut out, fields.len() as u64);
+    for &(field, tools) in fields {
+        wasm_name(&mut out, field);
+        uleb(&mut out, tools.len() as u64);
+        for &(name, version) in tools {
+            wasm_name(&mut out, name);
+            wasm_name(&mut out, version);
+        }
+    }
+    out
+}
+
+fn wasm_name(out: &mut Vec<u8>, name: &str) {
+    uleb(out, name.len() as u64);
+    out.extend(name.as_bytes());
+}
+
+/// The variants of `ar` archives.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Ar {
+    /// GNU `ar`, which puts names longer than 15 bytes in a `//` member.
+    Gnu,
+    /// BSD and macOS `ar`, which put names longer than 16 bytes, and any with
+    /// spaces, right before the member's data, as `#1/<length>`.
+    Bsd,
+    /// GNU `ar --thin`, which only has the names of its members' files, all of
+    /// them in the `//` member.
+    Thin,
+}
+
+/// Make an `ar` archive with the given members, starting with an empty symbol
+/// table like `ar rcs` writes.
+pub fn ar(kind: Ar, members: &[(&str, Vec<u8>)]) -> Vec<u8> {
+    let mut out = if kind == Ar::Thin {
+        b"!<thin>\n".to_vec()
+    } else {
+        b"!<arch>\n".to_vec()
+    };
+
+    if kind == Ar::Bsd {
+        let mut symdef = b"__.SYMDEF SORTED\0\0\0\0".to_vec();
+        symdef.extend(&[0; 8]);
+        ar_member(&mut out, "#1/20", &symdef, symdef.len());
+        for &(name, ref data) in members {
+            let mut padded = name.as_bytes().to_vec();
+            padded.resize((name.len() + 7) & !7, 0);
+            let header_name = format!("#1/{}", padded.len());
+            padded.extend(data);
+            ar_member(&mut out, &header_name, &padded, padded.len());
+        }
+        return out;
+    }
+
+    ar_member(&mut out, "/", &[0; 4], 4);
+    let mut names = vec![];
+    let mut header_names = vec![];
+    for &(name, _) in members {
+        if kind == Ar::Thin || name.len() > 15 {
+            header_names.push(format!("/{}", names.len()));
+            names.extend(name.as_bytes());
+            names.extend(b"/\n");
+        } else {
+            header_names.push(format!("{}/", name));
+        }
+    }
+    if !names.is_empty() {
+        ar_member(&mut out, "//", &names, names.len());
+    }
+    for ((_, data), header_name) in members.iter().zip(&header_names) {
+        let contents: &[u8] = if kind == Ar::Thin { &[] } else { data };
+        ar_member(&mut out, header_name, contents, data.len());
+    }
+    out
+}
+
+/// Append an archive member's header, claiming a size of `size`, and then its
+/// contents, padded to an even length.
+fn ar_member(out: &mut Vec<u8>, name: &str, contents: &[u8], size: usize) {
+    let header = format!("{:<16}{:<12}{:<6}{:<6}{:<8}{:<10}`\n", name, 0, 0, 0, 644, size);
+    assert_eq!(header.len(), 60);
+    out.extend(header.as_bytes());
+    out.extend(contents);
+    if contents.len() % 2 == 1 {
+        out.push(b'\n');
+    }
+}
+
+/// Write the given fixture data into the test scratch directory and return
+/// its path. The name may contain directories, which are created as needed.
+pub fn write_fixture(name: &str, data: &[u8]) -> PathBuf {
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+    fs::create_dir_all(path.parent().unwrap()).expect("should create fixture directory");
+    fs::write(&path, data).expect("should write fixture");
+    path
+}
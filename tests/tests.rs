@@ -1,6 +1,27 @@
+extern crate crc32fast;
+extern crate dwprod;
+extern crate fallible_iterator;
+extern crate flate2;
+extern crate gimli;
+extern crate object;
+extern crate ruzstd;
+extern crate serde_json;
+
+mod support;
+
+use fallible_iterator::FallibleIterator;
+use support::fixtures;
+use std::path::Path;
 use std::process::Command;
 use std::str;
 
+fn producers_of(path: &Path) -> Vec<String> {
+    dwprod::Options::new(path)
+        .producers(|producers| producers.collect())
+        .expect("should open fixture")
+        .expect("should read producers")
+}
+
 #[test]
 #[cfg(feature = "exe")]
 fn dwprod_has_rustc_producer() {
@@ -25,3 +46,2188 @@ fn dwprod_has_rustc_producer() {
             .contains("rustc")
     );
 }
+
+/// A fixture with a rustc unit, a unit whose producer can't be read, and a
+/// unit without a producer.
+#[cfg(feature = "exe")]
+fn json_fixture(name: &str) -> std::path::PathBuf {
+    use support::{UnitSpec, Value};
+
+    let mut dwarf = support::DwarfBuilder::new(object::Endianness::Little);
+    dwarf.unit(
+        &UnitSpec::version(4)
+            .attr(gimli::DW_AT_name, Value::String("src/main.rs".into()))
+            .attr(gimli::DW_AT_language, Value::Data2(gimli::DW_LANG_Rust.0))
+            .attr(
+                gimli::DW_AT_producer,
+                Value::String("clang LLVM (rustc version 1.75.0 (82e1608df 2023-12-21))".into()),
+            ),
+    );
+    dwarf.unit(&UnitSpec::new("refers to .debug_str"));
+    dwarf.unit(&UnitSpec::version(5).attr(gimli::DW_AT_name, Value::String("empty.c".into())));
+
+    let data = support::Elf::new().dwarf(&dwarf).without(".debug_str").write();
+    support::write_fixture(name, &data)
+}
+
+#[test]
+#[cfg(feature = "exe")]
+fn json_output() {
+    let path = json_fixture("json-output.o");
+    let output = Command::new(env!("DWPROD_EXE"))
+        .arg("--format")
+        .arg("json")
+        .arg(&path)
+        .output()
+        .expect("should run ok");
+    assert!(!output.status.success());
+
+    let document: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(document["schema_version"], 2);
+    assert_eq!(document["files"].as_array().unwrap().len(), 1);
+
+    let json = &document["files"][0];
+    assert_eq!(json["file"], path.to_str().unwrap());
+    assert_eq!(
+        json["producers"],
+        serde_json::json!(["clang LLVM (rustc version 1.75.0 (82e1608df 2023-12-21))"])
+    );
+    assert_eq!(json["errors"].as_array().unwrap().len(), 1);
+    assert!(json["errors"][0].as_str().unwrap().contains(".debug_str"));
+
+    let units = json["units"].as_array().unwrap();
+    assert_eq!(units.len(), 2);
+    assert_eq!(
+        units[0],
+        serde_json::json!({
+            "kind": "compile",
+            "offset": 0,
+            "version": 4,
+            "address_size": 8,
+            "name": "src/main.rs",
+            "comp_dir": null,
+            "language": gimli::DW_LANG_Rust.0,
+            "language_name": "DW_LANG_Rust",
+            "producer": {
+                "raw": "clang LLVM (rustc version 1.75.0 (82e1608df 2023-12-21))",
+                "compiler": "rustc",
+                "version": "1.75.0",
+                "rustc": {
+                    "channel": "stable",
+                    "commit_hash": "82e1608df",
+                    "commit_date": "2023-12-21",
+                },
+                "flags": [],
+            },
+        })
+    );
+    assert_eq!(units[1]["name"], "empty.c");
+    assert_eq!(units[1]["version"], 5);
+    assert_eq!(units[1]["producer"], serde_json::Value::Null);
+}
+
+#[test]
+#[cfg(feature = "exe")]
+fn ndjson_output() {
+    let path = json_fixture("ndjson-output.o");
+    let output = Command::new(env!("DWPROD_EXE"))
+        .arg("--format=ndjson")
+        .arg(&path)
+        .output()
+        .expect("should run ok");
+    assert!(!output.status.success());
+
+    let lines: Vec<serde_json::Value> = str::from_utf8(&output.stdout)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 3);
+    for line in &lines {
+        assert_eq!(line["schema_version"], 2);
+        assert_eq!(line["file"], path.to_str().unwrap());
+    }
+    assert_eq!(lines[0]["unit"]["producer"]["compiler"], "rustc");
+    assert!(lines[1]["error"].as_str().unwrap().contains(".debug_str"));
+    assert_eq!(lines[2]["unit"]["name"], "empty.c");
+}
+
+/// What a `dwprod` invocation is expected to print to stdout.
+#[cfg(feature = "exe")]
+enum Stdout {
+    /// Exactly this text.
+    Text(String),
+    /// A JSON document with these values at these JSON pointers.
+    Json(Vec<(&'static str, serde_json::Value)>),
+}
+
+/// A `dwprod` invocation: the arguments before the file, the file, whether
+/// `dwprod` should succeed, what it should print to stdout, and the start of
+/// each line it should print to stderr.
+#[cfg(feature = "exe")]
+type Invocation<'a> = (&'a [&'a str], &'a Path, bool, Stdout, Vec<String>);
+
+/// Each command line flag, and each kind of file whose output differs, is
+/// plumbed through to the output. What the library finds in each kind of file
+/// is tested with the library itself.
+#[test]
+#[cfg(feature = "exe")]
+fn command_line_flags() {
+    use serde_json::json;
+
+    let universal = support::write_fixture("cli/universal", &fixtures::universal_binary());
+    let wasm = fixtures::wasm_module("clang version 17.0.6");
+    let wasm = support::write_fixture("cli/app.wasm", &wasm);
+    let truncated = fixtures::wasm_module_with_truncated_producers("clang version 17.0.6");
+    let truncated = support::write_fixture("cli/truncated.wasm", &truncated);
+    let library = fixtures::static_library(support::Ar::Gnu);
+    let library = support::write_fixture("cli/libab.a", &library);
+    let member = support::Elf::new().producers(&["GNU C17 12.2.0 -g"]).write();
+    let single = support::ar(support::Ar::Gnu, &[("a.o", member)]);
+    let single = support::write_fixture("cli/liba.a", &single);
+    let stripped = support::write_fixture("cli/stripped", &fixtures::stripped_binary());
+    let type_units = support::write_fixture("cli/type-units", &fixtures::type_units_object().0);
+    let (damaged, broken) = fixtures::damaged_object();
+    let damaged = support::write_fixture("cli/damaged", &damaged);
+    let warning = |offset: u64| format!("Warning: unit at .debug_info+{:#x}: ", offset);
+    let error = |offset: u64| format!("Error: unit at .debug_info+{:#x}: ", offset);
+
+    let cases: Vec<Invocation> = vec![
+        // Even a single universal binary is prefixed, with each architecture.
+        (
+            &[],
+            &universal,
+            true,
+            Stdout::Text(format!(
+                "{0} (x86_64): clang for x86_64\n{0} (arm64): clang for arm64\n",
+                universal.display()
+            )),
+            vec![],
+        ),
+        (
+            &["--format", "json"],
+            &universal,
+            true,
+            Stdout::Json(vec![
+                ("/files/0/arch", json!("x86_64")),
+                ("/files/1/arch", json!("arm64")),
+            ]),
+            vec![],
+        ),
+        (
+            &[],
+            &wasm,
+            true,
+            Stdout::Text(
+                "clang version 17.0.6\n\
+                 language: Rust\n\
+                 processed-by: rustc 1.75.0 (82e1608df 2023-12-21)\n\
+                 processed-by: wasm-bindgen 0.2.89\n"
+                    .into(),
+            ),
+            vec![],
+        ),
+        (
+            &["--format", "json"],
+            &wasm,
+            true,
+            Stdout::Json(vec![(
+                "/files/0/wasm_producers",
+                json!({
+                    "language": [{ "name": "Rust", "version": "" }],
+                    "processed_by": [
+                        { "name": "rustc", "version": "1.75.0 (82e1608df 2023-12-21)" },
+                        { "name": "wasm-bindgen", "version": "0.2.89" },
+                    ],
+                    "sdk": [],
+                }),
+            )]),
+            vec![],
+        ),
+        // A malformed `producers` section is only a warning.
+        (
+            &[],
+            &truncated,
+            true,
+            Stdout::Text("clang version 17.0.6\n".into()),
+            vec!["Warning: producers section: ".into()],
+        ),
+        (
+            &["--format", "json"],
+            &truncated,
+            true,
+            Stdout::Json(vec![
+                ("/files/0/producers", json!(["clang version 17.0.6"])),
+                ("/files/0/wasm_producers", json!(null)),
+                (
+                    "/files/0/wasm_producers_error",
+                    json!("Hit the end of input before it was expected"),
+                ),
+            ]),
+            vec![],
+        ),
+        (
+            &[],
+            &library,
+            true,
+            Stdout::Text(format!(
+                "{0}(a.o): GNU C17 12.2.0 -g\n\
+                 {0}(a_rather_long_member_name.o): clang version 17.0.6\n",
+                library.display()
+            )),
+            vec![],
+        ),
+        (
+            &["--format", "json"],
+            &library,
+            true,
+            Stdout::Json(vec![
+                ("/files/0/member", json!("a.o")),
+                ("/files/1/member", json!("a_rather_long_member_name.o")),
+            ]),
+            vec![],
+        ),
+        // Even a single archive member is prefixed, with its name.
+        (
+            &[],
+            &single,
+            true,
+            Stdout::Text(format!("{}(a.o): GNU C17 12.2.0 -g\n", single.display())),
+            vec![],
+        ),
+        (
+            &[],
+            &stripped,
+            false,
+            Stdout::Text("".into()),
+            vec!["Error: missing .debug_info section".into()],
+        ),
+        (
+            &["--toolchain-fallback"],
+            &stripped,
+            true,
+            Stdout::Text(
+                ".comment: GCC: (GNU) 12.2.0\n\
+                 .comment: rustc version 1.75.0 (82e1608df 2023-12-21)\n\
+                 .comment: Linker: LLD 17.0.6\n\
+                 .note.gnu.property: x86 feature: IBT, SHSTK\n\
+                 .note.gnu.property: x86 ISA needed: x86-64-baseline\n"
+                    .into(),
+            ),
+            vec![],
+        ),
+        (
+            &["--toolchain-fallback", "--format", "json"],
+            &stripped,
+            true,
+            Stdout::Json(vec![(
+                "/files/0/toolchain_records/0",
+                json!({ "source": ".comment", "text": "GCC: (GNU) 12.2.0" }),
+            )]),
+            vec![],
+        ),
+        (
+            &[],
+            &type_units,
+            true,
+            Stdout::Text(
+                "clang version 17.0.6 -gdwarf-4\n\
+                 clang version 17.0.6 -gdwarf-5\n"
+                    .into(),
+            ),
+            vec![],
+        ),
+        (
+            &["--type-units"],
+            &type_units,
+            true,
+            Stdout::Text(
+                "clang version 17.0.6 -gdwarf-4\n\
+                 type unit: clang version 17.0.6 -gdwarf-5 -fdebug-types-section\n\
+                 clang version 17.0.6 -gdwarf-5\n\
+                 type unit: clang version 17.0.6 -gdwarf-4 -fdebug-types-section\n"
+                    .into(),
+            ),
+            vec![],
+        ),
+        (
+            &["--type-units", "--format", "json"],
+            &type_units,
+            true,
+            Stdout::Json(vec![
+                ("/files/0/units/0/kind", json!("compile")),
+                ("/files/0/units/1/kind", json!("type")),
+                ("/files/0/units/2/kind", json!("compile")),
+                ("/files/0/units/3/kind", json!("type")),
+            ]),
+            vec![],
+        ),
+        // A corrupt unit header ends the section, and is an error.
+        (
+            &[],
+            &damaged,
+            false,
+            Stdout::Text("GNU C17 13.2.0 -g\n".into()),
+            vec![error(broken[0])],
+        ),
+        (
+            &["--lenient"],
+            &damaged,
+            true,
+            Stdout::Text(
+                "GNU C17 13.2.0 -g\n\
+                 clang version 17.0.6\n\
+                 GNU C17 13.2.0 -O2\n"
+                    .into(),
+            ),
+            broken.iter().map(|&offset| warning(offset)).collect(),
+        ),
+        (
+            &["--lenient", "--format", "json"],
+            &damaged,
+            true,
+            Stdout::Json(vec![(
+                "/files/0/diagnostics/1",
+                json!({
+                    "section": ".debug_info",
+                    "offset": broken[1],
+                    "error": "missing .debug_str section",
+                }),
+            )]),
+            vec![],
+        ),
+    ];
+
+    for (args, file, success, stdout, stderr) in cases {
+        let output = Command::new(env!("DWPROD_EXE")).args(args).arg(file).output().unwrap();
+        let context = format!("dwprod {} {}", args.join(" "), file.display());
+        assert_eq!(output.status.success(), success, "{}", context);
+
+        let actual = str::from_utf8(&output.stdout).unwrap();
+        match stdout {
+            Stdout::Text(expected) => assert_eq!(actual, expected, "{}", context),
+            Stdout::Json(expected) => {
+                let document: serde_json::Value = serde_json::from_str(actual).unwrap();
+                for (pointer, value) in expected {
+                    assert_eq!(document.pointer(pointer), Some(&value), "{}: {}", context, pointer);
+                }
+            }
+        }
+
+        let lines: Vec<_> = str::from_utf8(&output.stderr).unwrap().lines().collect();
+        assert_eq!(lines.len(), stderr.len(), "{}: {:?}", context, lines);
+        for (line, start) in lines.iter().zip(&stderr) {
+            assert!(line.starts_with(start), "{}: {:?}", context, line);
+        }
+    }
+}
+
+fn assert_big_endian_producers(name: &str, arch: object::Architecture) {
+    let expected = vec!["GNU C11 12.2.0 -mbig-endian", "clang version 17.0.6"];
+    let data = support::Elf::target(arch, object::Endianness::Big)
+        .producers(&expected)
+        .write();
+    let path = support::write_fixture(name, &data);
+    assert_eq!(producers_of(&path), expected);
+}
+
+#[test]
+fn big_endian_powerpc64() {
+    assert_big_endian_producers("big-endian-ppc64.o", object::Architecture::PowerPc64);
+}
+
+#[test]
+fn big_endian_s390x() {
+    assert_big_endian_producers("big-endian-s390x.o", object::Architecture::S390x);
+}
+
+#[test]
+fn big_endian_mips() {
+    assert_big_endian_producers("big-endian-mips.o", object::Architecture::Mips);
+}
+
+#[test]
+fn little_endian_aarch64() {
+    let expected = vec!["GNU C17 12.2.0 -mlittle-endian"];
+    let data = support::Elf::target(object::Architecture::Aarch64, object::Endianness::Little)
+        .producers(&expected)
+        .write();
+    let path = support::write_fixture("little-endian-aarch64.o", &data);
+    assert_eq!(producers_of(&path), expected);
+}
+
+fn assert_relocated_producers(name: &str, arch: object::Architecture) {
+    use support::{UnitSpec, Value};
+
+    let mut dwarf = support::DwarfBuilder::new(object::Endianness::Little);
+    dwarf
+        .unit(&UnitSpec::new("GNU C17 13.2.0 -g").attr(gimli::DW_AT_name, Value::Strp("a.c".into())))
+        .unit(
+            &UnitSpec::version(5)
+                .attr(gimli::DW_AT_producer, Value::Strp("clang version 17.0.6".into()))
+                .attr(gimli::DW_AT_name, Value::LineStrp("b.c".into())),
+        );
+    assert!(!dwarf.relocations().is_empty());
+
+    // Every reference into another section is zero until it is relocated, so
+    // without relocating, both units would have the first unit's
+    // abbreviations, and every string would be the first string.
+    let data = support::Elf::target(arch, object::Endianness::Little)
+        .unlinked_dwarf(&dwarf)
+        .write();
+    let path = support::write_fixture(name, &data);
+    let units: Vec<(String, String)> = dwprod::Options::new(&path)
+        .compilation_units(|units| {
+            units
+                .map(|unit| {
+                    let producer = unit.producer().unwrap().raw().to_string();
+                    (producer, unit.name().unwrap().to_string())
+                })
+                .collect()
+        })
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        units,
+        vec![
+            ("GNU C17 13.2.0 -g".into(), "a.c".into()),
+            ("clang version 17.0.6".into(), "b.c".into()),
+        ]
+    );
+}
+
+#[test]
+fn relocatable_x86_64_objects() {
+    assert_relocated_producers("relocatable-x86_64.o", object::Architecture::X86_64);
+}
+
+#[test]
+fn relocatable_aarch64_objects() {
+    assert_relocated_producers("relocatable-aarch64.o", object::Architecture::Aarch64);
+}
+
+#[test]
+fn relocatable_riscv64_objects() {
+    assert_relocated_producers("relocatable-riscv64.o", object::Architecture::Riscv64);
+}
+
+/// Get each unit's offset, producer and name.
+fn unit_summaries(path: &Path) -> Vec<(u64, String, String)> {
+    dwprod::Options::new(path)
+        .compilation_units(|units| {
+            units
+                .map(|unit| {
+                    let producer = unit.producer().unwrap().raw().to_string();
+                    (unit.offset(), producer, unit.name().unwrap().to_string())
+                })
+                .collect()
+        })
+        .unwrap()
+        .unwrap()
+}
+
+#[test]
+fn dwarf64_units() {
+    use support::{UnitSpec, Value};
+
+    // Every reference into another section is 8 bytes in the 64-bit format,
+    // including those in the `.debug_str_offsets` contribution.
+    let mut dwarf = support::DwarfBuilder::new(object::Endianness::Little);
+    dwarf.unit(&UnitSpec {
+        dwarf64: true,
+        ..UnitSpec::new("GNU C17 13.2.0 -gdwarf64")
+            .attr(gimli::DW_AT_name, Value::Strp("a.c".into()))
+    });
+    dwarf.unit(&UnitSpec {
+        dwarf64: true,
+        ..UnitSpec::version(5)
+            .attr(
+                gimli::DW_AT_producer,
+                Value::Strx(gimli::DW_FORM_strx1, "GNU C17 13.2.0 -gdwarf-5 -gdwarf64".into()),
+            )
+            .attr(gimli::DW_AT_name, Value::LineStrp("b.c".into()))
+    });
+    let second_offset = dwarf.last_unit_offset();
+    let expected = vec![
+        (0, "GNU C17 13.2.0 -gdwarf64".to_string(), "a.c".to_string()),
+        (
+            second_offset,
+            "GNU C17 13.2.0 -gdwarf-5 -gdwarf64".to_string(),
+            "b.c".to_string(),
+        ),
+    ];
+
+    let path = support::write_fixture("dwarf64/app", &support::Elf::new().dwarf(&dwarf).write());
+    assert_eq!(unit_summaries(&path), expected);
+
+    // Unlinked, the references are 64-bit relocations.
+    assert!(dwarf.relocations().iter().all(|relocation| relocation.size == 8));
+    let data = support::Elf::new().unlinked_dwarf(&dwarf).write();
+    let path = support::write_fixture("dwarf64/app.o", &data);
+    assert_eq!(unit_summaries(&path), expected);
+}
+
+#[test]
+fn mixed_dwarf32_and_dwarf64_units() {
+    use support::{UnitSpec, Value};
+
+    // Linking objects built with and without `-gdwarf64` mixes the formats
+    // within one `.debug_info` section, here in a big-endian file to make
+    // sure the 64-bit lengths are read with the right endianness too.
+    let mut dwarf = support::DwarfBuilder::new(object::Endianness::Big);
+    let mut expected = vec![];
+    let formats = [(4, false), (4, true), (5, false), (5, true)];
+    for (i, &(version, dwarf64)) in formats.iter().enumerate() {
+        let format = if dwarf64 { " -gdwarf64" } else { "" };
+        let producer = format!("GNU C17 13.2.0 -gdwarf-{}{}", version, format);
+        let name = format!("{}.c", i);
+        dwarf.unit(&UnitSpec {
+            dwarf64,
+            ..UnitSpec::version(version)
+                .attr(gimli::DW_AT_producer, Value::Strp(producer.clone()))
+                .attr(gimli::DW_AT_name, Value::Strp(name.clone()))
+        });
+        expected.push((dwarf.last_unit_offset(), producer, name));
+    }
+
+    let data = support::Elf::target(object::Architecture::PowerPc64, object::Endianness::Big)
+        .dwarf(&dwarf)
+        .write();
+    let path = support::write_fixture("mixed-dwarf64.o", &data);
+    assert_eq!(unit_summaries(&path), expected);
+}
+
+fn assert_32_bit_target(name: &str, arch: object::Architecture) {
+    use support::{UnitSpec, Value};
+
+    // The `DW_AT_low_pc` is read with the unit's address size, so reading it
+    // with the wrong one would misplace the attributes after it.
+    let unit = |producer: &str, name: &str| UnitSpec {
+        address_size: 4,
+        ..UnitSpec::version(4)
+            .attr(gimli::DW_AT_low_pc, Value::Addr(0x1_0000))
+            .attr(gimli::DW_AT_producer, Value::Strp(producer.into()))
+            .attr(gimli::DW_AT_name, Value::Strp(name.into()))
+    };
+    let mut dwarf = support::DwarfBuilder::new(object::Endianness::Little);
+    dwarf.unit(&unit("GNU C17 13.2.0 -g", "a.c"));
+    dwarf.unit(&UnitSpec {
+        version: 5,
+        ..unit("clang version 17.0.6", "b.c")
+    });
+    let second_offset = dwarf.last_unit_offset();
+    let expected = vec![
+        (0, "GNU C17 13.2.0 -g".to_string(), "a.c".to_string()),
+        (second_offset, "clang version 17.0.6".to_string(), "b.c".to_string()),
+    ];
+
+    let data = support::Elf::target(arch, object::Endianness::Little).dwarf(&dwarf).write();
+    let path = support::write_fixture(&format!("{}/app", name), &data);
+    assert_eq!(unit_summaries(&path), expected);
+    let units = dwprod::Options::new(&path)
+        .compilation_units(|units| units.collect::<Vec<_>>())
+        .unwrap()
+        .unwrap();
+    assert!(units.iter().all(|unit| unit.address_size() == 4));
+
+    // These targets' relocations keep their addends in the section data.
+    let data = support::Elf::target(arch, object::Endianness::Little)
+        .unlinked_dwarf(&dwarf)
+        .write();
+    let path = support::write_fixture(&format!("{}/app.o", name), &data);
+    assert_eq!(unit_summaries(&path), expected);
+}
+
+#[test]
+fn armv7_targets() {
+    assert_32_bit_target("armv7", object::Architecture::Arm);
+}
+
+#[test]
+fn i686_targets() {
+    assert_32_bit_target("i686", object::Architecture::I386);
+}
+
+#[test]
+fn dwarf5_string_forms() {
+    use support::{UnitSpec, Value};
+
+    let forms = [
+        gimli::DW_FORM_strx,
+        gimli::DW_FORM_strx1,
+        gimli::DW_FORM_strx2,
+        gimli::DW_FORM_strx3,
+        gimli::DW_FORM_strx4,
+    ];
+
+    let mut dwarf = support::DwarfBuilder::new(object::Endianness::Little);
+    let mut expected = vec![];
+    for (i, form) in forms.iter().enumerate() {
+        let producer = format!("clang version 17.0.6 (strx form {})", i);
+        dwarf.unit(
+            &UnitSpec::version(5)
+                // Put a strx name before the producer, so that the producer
+                // doesn't live at index zero.
+                .attr(gimli::DW_AT_name, Value::Strx(*form, "lib.c".into()))
+                .attr(gimli::DW_AT_producer, Value::Strx(*form, producer.clone())),
+        );
+        expected.push(producer);
+    }
+    dwarf.unit(
+        &UnitSpec::version(5)
+            .attr(gimli::DW_AT_producer, Value::LineStrp("GNU C17 12.2.0 -gdwarf-5".into())),
+    );
+    expected.push("GNU C17 12.2.0 -gdwarf-5".into());
+    dwarf.unit(
+        &UnitSpec::version(5)
+            .attr(gimli::DW_AT_producer, Value::Strp("GNU C17 13.1.0 -gdwarf-5".into())),
+    );
+    expected.push("GNU C17 13.1.0 -gdwarf-5".into());
+
+    let data = support::Elf::new().dwarf(&dwarf).write();
+    let path = support::write_fixture("dwarf5-string-forms.o", &data);
+    assert_eq!(producers_of(&path), expected);
+}
+
+#[test]
+fn compilation_unit_records() {
+    use dwprod::Compiler;
+    use support::{UnitSpec, Value};
+
+    let comp_dir = fixture_dir("records");
+
+    let mut dwo = support::DwarfBuilder::new(object::Endianness::Little);
+    dwo.unit(
+        &UnitSpec::split(gimli::DW_UT_split_compile, 0x5eed)
+            .attr(gimli::DW_AT_name, Value::Strx(gimli::DW_FORM_strx1, "split.c".into()))
+            .attr(gimli::DW_AT_language, Value::Data1(gimli::DW_LANG_C11.0 as u8))
+            .attr(
+                gimli::DW_AT_producer,
+                Value::Strx(gimli::DW_FORM_strx1, "GNU C17 12.2.0 -gsplit-dwarf".into()),
+            ),
+    );
+    let data = support::Elf::new().debug_sections(dwo.dwo_sections()).write();
+    support::write_fixture("records/split.dwo", &data);
+
+    let mut dwarf = support::DwarfBuilder::new(object::Endianness::Little);
+    dwarf.unit(
+        &UnitSpec::new("clang LLVM (rustc version 1.75.0 (82e1608df 2023-12-21))")
+            .attr(gimli::DW_AT_name, Value::Strp("src/main.rs".into()))
+            .attr(gimli::DW_AT_comp_dir, Value::Strp("/home/user/app".into()))
+            .attr(gimli::DW_AT_language, Value::Data2(gimli::DW_LANG_Rust.0)),
+    );
+    dwarf.unit(&UnitSpec {
+        address_size: 4,
+        ..UnitSpec::version(5).attr(gimli::DW_AT_name, Value::String("no-producer.c".into()))
+    });
+    // A DWARF 5 compilation unit header is 12 bytes.
+    let second_offset = dwarf.last_root_offset() - 12;
+    dwarf.unit(
+        &UnitSpec::split(gimli::DW_UT_skeleton, 0x5eed)
+            .attr(gimli::DW_AT_comp_dir, Value::Strp(comp_dir.clone()))
+            .attr(gimli::DW_AT_dwo_name, Value::Strp("split.dwo".into())),
+    );
+    let path = support::write_fixture("records/app", &support::Elf::new().dwarf(&dwarf).write());
+
+    let units = dwprod::Options::new(&path)
+        .compilation_units(|units| units.collect::<Vec<_>>())
+        .unwrap()
+        .unwrap();
+    assert_eq!(units.len(), 3);
+
+    assert_eq!(units[0].offset(), 0);
+    assert_eq!(units[0].version(), 4);
+    assert_eq!(units[0].address_size(), 8);
+    assert_eq!(units[0].name(), Some("src/main.rs"));
+    assert_eq!(units[0].comp_dir(), Some("/home/user/app"));
+    assert_eq!(units[0].language(), Some(gimli::DW_LANG_Rust.0));
+    assert_eq!(units[0].language_name(), Some("DW_LANG_Rust"));
+    let producer = units[0].producer().unwrap();
+    assert_eq!(producer.compiler(), Compiler::Rustc);
+    assert_eq!(
+        producer.raw(),
+        "clang LLVM (rustc version 1.75.0 (82e1608df 2023-12-21))"
+    );
+
+    assert_eq!(units[1].offset(), second_offset);
+    assert_eq!(units[1].version(), 5);
+    assert_eq!(units[1].address_size(), 4);
+    assert_eq!(units[1].name(), Some("no-producer.c"));
+    assert_eq!(units[1].comp_dir(), None);
+    assert_eq!(units[1].language(), None);
+    assert_eq!(units[1].producer(), None);
+
+    // The skeleton unit's record is filled in from its split unit.
+    assert_eq!(units[2].version(), 5);
+    assert_eq!(units[2].name(), Some("split.c"));
+    assert_eq!(units[2].comp_dir(), Some(&comp_dir[..]));
+    assert_eq!(units[2].language_name(), Some("DW_LANG_C11"));
+    assert_eq!(units[2].producer().unwrap().compiler(), Compiler::Gcc);
+
+    // The producers convenience iterator skips units without a producer.
+    assert_eq!(
+        producers_of(&path),
+        vec![
+            "clang LLVM (rustc version 1.75.0 (82e1608df 2023-12-21))",
+            "GNU C17 12.2.0 -gsplit-dwarf",
+        ]
+    );
+}
+
+/// Something that keeps loaded debug info around to inspect later.
+struct Inventory {
+    binaries: Vec<dwprod::DebugInfo>,
+}
+
+fn load_debug_info(path: &Path) -> dwprod::Result<dwprod::DebugInfo> {
+    dwprod::Options::new(path).load()
+}
+
+#[test]
+fn owned_debug_info() {
+    use support::{UnitSpec, Value};
+
+    let mut dwarf = support::DwarfBuilder::new(object::Endianness::Little);
+    dwarf.unit(&UnitSpec::new("GNU C17 12.2.0 -g"));
+    dwarf.unit(
+        &UnitSpec::version(5)
+            .attr(gimli::DW_AT_name, Value::Strp("main.rs".into()))
+            .attr(
+                gimli::DW_AT_producer,
+                Value::Strp("clang LLVM (rustc version 1.75.0 (82e1608df 2023-12-21))".into()),
+            ),
+    );
+    let path = support::write_fixture("owned.o", &support::Elf::new().dwarf(&dwarf).write());
+
+    let inventory = Inventory {
+        binaries: vec![load_debug_info(&path).unwrap()],
+    };
+    let info = &inventory.binaries[0];
+    assert_eq!(info.path(), Some(path.as_path()));
+
+    let expected = vec![
+        "GNU C17 12.2.0 -g",
+        "clang LLVM (rustc version 1.75.0 (82e1608df 2023-12-21))",
+    ];
+
+    // Each iterator starts from the first unit, and iterators can outlive the
+    // `DebugInfo` they came from.
+    let mut producers = info.producers();
+    assert_eq!(producers.next().unwrap().unwrap(), expected[0]);
+    assert_eq!(info.producers().collect::<Vec<_>>().unwrap(), expected);
+    let units = info.compilation_units();
+    drop(inventory);
+    assert_eq!(producers.collect::<Vec<_>>().unwrap(), &expected[1..]);
+    assert_eq!(
+        units
+            .map(|unit| unit.name().map(String::from))
+            .collect::<Vec<_>>()
+            .unwrap(),
+        vec![None, Some("main.rs".to_string())]
+    );
+
+    // Debug info can be sent to, and shared with, other threads.
+    let info = load_debug_info(&path).unwrap();
+    let handle = {
+        let info = info.clone();
+        std::thread::spawn(move || info.producers().count().unwrap())
+    };
+    assert_eq!(handle.join().unwrap(), 2);
+    assert_eq!(info.producers().count().unwrap(), 2);
+}
+
+#[test]
+fn inline_string_producers_without_debug_str() {
+    use support::{UnitSpec, Value};
+
+    let mut dwarf = support::DwarfBuilder::new(object::Endianness::Little);
+    dwarf.unit(
+        &UnitSpec::version(4)
+            .attr(gimli::DW_AT_producer, Value::String("GNU C99 4.8.5 -g".into())),
+    );
+    dwarf.unit(
+        &UnitSpec::version(5)
+            .attr(gimli::DW_AT_producer, Value::String("GNU C17 12.2.0 -g".into())),
+    );
+    assert!(
+        dwarf
+            .sections()
+            .iter()
+            .all(|&(name, _)| name != ".debug_str")
+    );
+
+    let data = support::Elf::new().dwarf(&dwarf).write();
+    let path = support::write_fixture("inline-string-producers.o", &data);
+    assert_eq!(
+        producers_of(&path),
+        vec!["GNU C99 4.8.5 -g", "GNU C17 12.2.0 -g"]
+    );
+}
+
+#[test]
+fn missing_debug_str_is_a_per_unit_error() {
+    use support::{UnitSpec, Value};
+
+    let mut dwarf = support::DwarfBuilder::new(object::Endianness::Little);
+    dwarf.unit(&UnitSpec::version(4).attr(gimli::DW_AT_producer, Value::String("first".into())));
+    dwarf.unit(&UnitSpec::new("refers to .debug_str"));
+    dwarf.unit(&UnitSpec::version(4).attr(gimli::DW_AT_producer, Value::String("third".into())));
+
+    let data = support::Elf::new().dwarf(&dwarf).without(".debug_str").write();
+    let path = support::write_fixture("missing-debug-str.o", &data);
+
+    dwprod::Options::new(&path)
+        .producers(|producers| {
+            assert_eq!(producers.next().unwrap(), Some("first".into()));
+            let err = producers.next().unwrap_err();
+            assert!(err.to_string().contains(".debug_str"));
+            assert_eq!(producers.next().unwrap(), Some("third".into()));
+            assert_eq!(producers.next().unwrap(), None);
+        })
+        .unwrap();
+}
+
+#[cfg(feature = "compression")]
+fn assert_compressed_producers(name: &str, compression: support::Compression) {
+    use support::{UnitSpec, Value};
+
+    let mut dwarf = support::DwarfBuilder::new(object::Endianness::Little);
+    dwarf.unit(&UnitSpec::new("GNU C17 12.2.0 -gz=zlib"));
+    dwarf.unit(
+        &UnitSpec::version(5)
+            .attr(gimli::DW_AT_name, Value::LineStrp("main.c".into()))
+            .attr(gimli::DW_AT_producer, Value::Strx(gimli::DW_FORM_strx1, "clang 17".into())),
+    );
+
+    let data = support::Elf::new().dwarf(&dwarf).compressed(compression).write();
+    let path = support::write_fixture(name, &data);
+    assert_eq!(
+        producers_of(&path),
+        vec!["GNU C17 12.2.0 -gz=zlib", "clang 17"]
+    );
+}
+
+#[test]
+#[cfg(feature = "compression")]
+fn compressed_gnu_zdebug_sections() {
+    assert_compressed_producers("compressed-zdebug.o", support::Compression::GnuZlib);
+}
+
+#[test]
+#[cfg(feature = "compression")]
+fn compressed_zlib_sections() {
+    assert_compressed_producers("compressed-zlib.o", support::Compression::Zlib);
+}
+
+#[test]
+#[cfg(feature = "compression")]
+fn compressed_zstd_sections() {
+    assert_compressed_producers("compressed-zstd.o", support::Compression::Zstd);
+}
+
+#[test]
+fn unused_sections_are_not_read() {
+    // A `.debug_loc` section whose compressed data is corrupt, which would be
+    // an error if it were decompressed.
+    let mut corrupt = b"ZLIB".to_vec();
+    corrupt.extend(&64u64.to_be_bytes());
+    corrupt.extend(b"not a zlib stream");
+    let data = support::Elf::new()
+        .producers(&["GNU C17 12.2.0 -gz=zlib"])
+        .section((".zdebug_loc", object::SectionKind::Debug, corrupt))
+        .write();
+    let path = support::write_fixture("unused-sections.o", &data);
+    assert_eq!(producers_of(&path), vec!["GNU C17 12.2.0 -gz=zlib"]);
+
+    let files = dwprod::Scan::new().path(&path).run();
+    assert_eq!(files[0].errors().count(), 0);
+}
+
+#[test]
+fn low_pc_in_debug_addr() {
+    use support::{UnitSpec, Value};
+
+    // Reading a unit resolves its root's `DW_AT_low_pc`, which DWARF 5 and
+    // the GNU split DWARF extension store as an index into `.debug_addr`.
+    let mut dwarf = support::DwarfBuilder::new(object::Endianness::Little);
+    dwarf
+        .unit(
+            &UnitSpec::version(5)
+                .attr(gimli::DW_AT_producer, Value::Strp("clang version 17.0.6".into()))
+                .attr(gimli::DW_AT_low_pc, Value::Addrx(gimli::DW_FORM_addrx, 0x1000)),
+        )
+        .unit(
+            &UnitSpec::new("GNU C17 12.2.0 -gsplit-dwarf")
+                .attr(gimli::DW_AT_low_pc, Value::Addrx(gimli::DW_FORM_GNU_addr_index, 0x2000)),
+        );
+    let data = support::Elf::new().dwarf(&dwarf).write();
+    let path = support::write_fixture("debug-addr.o", &data);
+    assert_eq!(
+        producers_of(&path),
+        vec!["clang version 17.0.6", "GNU C17 12.2.0 -gsplit-dwarf"]
+    );
+}
+
+#[test]
+fn separate_debug_file_by_build_id() {
+    let build_id = [0xab, 0xcd, 0xef, 0x01, 0x23, 0x45, 0x67, 0x89];
+    let exe = support::Elf::new().section(support::build_id_note(&build_id)).write();
+    let exe = support::write_fixture("build-id/bin/app", &exe);
+
+    // A debug file with a different build ID at the right path is ignored.
+    let other = support::Elf::new()
+        .section(support::build_id_note(&[0xab, 0, 0, 0]))
+        .producers(&["wrong"])
+        .write();
+    support::write_fixture("build-id/other-debug/.build-id/ab/cdef0123456789.debug", &other);
+
+    let debug = support::Elf::new()
+        .section(support::build_id_note(&build_id))
+        .producers(&["GNU C17 12.2.0 -g -O2"])
+        .write();
+    let debug = support::write_fixture("build-id/debug/.build-id/ab/cdef0123456789.debug", &debug);
+    let debug_dir = debug.ancestors().nth(3).unwrap();
+
+    let producers = dwprod::Options::new(&exe)
+        .debug_search_dir(debug_dir.with_file_name("other-debug"))
+        .debug_search_dir(debug_dir)
+        .producers(|producers| producers.collect::<Vec<_>>())
+        .unwrap()
+        .unwrap();
+    assert_eq!(producers, vec!["GNU C17 12.2.0 -g -O2"]);
+
+    let err = dwprod::Options::new(&exe)
+        .producers(|producers| producers.count())
+        .unwrap_err();
+    assert!(err.to_string().contains("missing .debug_info section"));
+}
+
+#[test]
+fn separate_debug_file_by_debuglink() {
+    let debug = support::Elf::new().producers(&["clang version 17.0.6"]).write();
+    support::write_fixture("debuglink/.debug/app.debug", &debug);
+
+    let exe = support::Elf::new().section(support::debuglink("app.debug", &debug)).write();
+    let exe = support::write_fixture("debuglink/app", &exe);
+    assert_eq!(producers_of(&exe), vec!["clang version 17.0.6"]);
+
+    // A debug file whose CRC doesn't match is ignored.
+    let exe = support::Elf::new()
+        .section(support::debuglink("app.debug", b"something else"))
+        .write();
+    let exe = support::write_fixture("debuglink/app-mismatched", &exe);
+    let err = dwprod::Options::new(&exe)
+        .producers(|producers| producers.count())
+        .unwrap_err();
+    assert!(err.to_string().contains("missing .debug_info section"));
+}
+
+#[test]
+fn in_memory_inputs() {
+    let data = support::Elf::new().producers(&["GNU C17 12.2.0 -g"]).write();
+    let expected = vec!["GNU C17 12.2.0 -g"];
+
+    let info = dwprod::Options::from_bytes(&data).load().unwrap();
+    assert_eq!(info.path(), None);
+    assert_eq!(info.producers().collect::<Vec<_>>().unwrap(), expected);
+
+    let info = dwprod::Options::from_vec(data.clone()).load().unwrap();
+    assert_eq!(info.producers().collect::<Vec<_>>().unwrap(), expected);
+
+    let producers = dwprod::Options::from_reader(&data[..])
+        .unwrap()
+        .producers(|producers| producers.collect::<Vec<_>>())
+        .unwrap()
+        .unwrap();
+    assert_eq!(producers, expected);
+
+    // Separate debug info files can still be found by build ID.
+    let build_id = [0x12, 0x34, 0x56, 0x78];
+    let debug = support::Elf::new()
+        .section(support::build_id_note(&build_id))
+        .producers(&["clang version 17.0.6"])
+        .write();
+    let debug = support::write_fixture("in-memory/debug/.build-id/12/345678.debug", &debug);
+    let exe = support::Elf::new().section(support::build_id_note(&build_id)).write();
+    let info = dwprod::Options::from_vec(exe)
+        .debug_search_dir(debug.ancestors().nth(3).unwrap())
+        .load()
+        .unwrap();
+    assert_eq!(
+        info.producers().collect::<Vec<_>>().unwrap(),
+        vec!["clang version 17.0.6"]
+    );
+
+    // Malformed data is an error, rather than a panic.
+    assert!(dwprod::Options::from_bytes(b"not an object file").load().is_err());
+}
+
+#[test]
+fn macho_debug_info() {
+    let data = fixtures::macho_with_producer(
+        object::Architecture::X86_64,
+        "Apple clang version 15.0.0 (clang-1500.1.0.2.5)",
+    );
+    let path = support::write_fixture("macho/app.o", &data);
+    assert_eq!(
+        producers_of(&path),
+        vec!["Apple clang version 15.0.0 (clang-1500.1.0.2.5)"]
+    );
+}
+
+#[test]
+fn dsym_bundles() {
+    let exe = support::write_fixture(
+        "dsym/app",
+        &support::macho(object::Architecture::X86_64, &[]),
+    );
+    support::write_fixture(
+        "dsym/app.dSYM/Contents/Resources/DWARF/app",
+        &fixtures::macho_with_producer(object::Architecture::X86_64, "Apple clang version 15.0.0"),
+    );
+
+    // The bundle next to a stripped binary is found automatically.
+    assert_eq!(producers_of(&exe), vec!["Apple clang version 15.0.0"]);
+    // Or the bundle itself can be given.
+    assert_eq!(
+        producers_of(&exe.with_file_name("app.dSYM")),
+        vec!["Apple clang version 15.0.0"]
+    );
+
+    // A bundle for another architecture is ignored.
+    let exe = support::write_fixture(
+        "dsym/arm64/app",
+        &support::macho(object::Architecture::Aarch64, &[]),
+    );
+    support::write_fixture(
+        "dsym/arm64/app.dSYM/Contents/Resources/DWARF/app",
+        &fixtures::macho_with_producer(object::Architecture::X86_64, "Apple clang version 15.0.0"),
+    );
+    let err = dwprod::Options::new(&exe).load().unwrap_err();
+    assert!(err.to_string().contains("missing .debug_info section"));
+
+    // A bundle with several files, none named after it, is ambiguous.
+    let dwarf = fixtures::macho_with_producer(object::Architecture::X86_64, "Apple clang");
+    support::write_fixture("dsym/ambiguous.dSYM/Contents/Resources/DWARF/a", &dwarf);
+    support::write_fixture("dsym/ambiguous.dSYM/Contents/Resources/DWARF/b", &dwarf);
+    let bundle = exe.parent().unwrap().parent().unwrap().join("ambiguous.dSYM");
+    match dwprod::Options::new(&bundle).load().unwrap_err() {
+        dwprod::Error::MissingDsymFile(dir) => {
+            assert_eq!(dir, bundle.join("Contents/Resources/DWARF"))
+        }
+        err => panic!("unexpected error: {:?}", err),
+    }
+
+    // Directory scans report the DWARF in a bundle once, along with its
+    // binary, and only report bundles on their own without one.
+    support::write_fixture("dsym/tree/app", &support::macho(object::Architecture::X86_64, &[]));
+    support::write_fixture("dsym/tree/app.dSYM/Contents/Resources/DWARF/app", &dwarf);
+    support::write_fixture("dsym/tree/lib.dylib.dSYM/Contents/Resources/DWARF/lib.dylib", &dwarf);
+    let root = exe.parent().unwrap().parent().unwrap().join("tree");
+    let found: Vec<_> = dwprod::Scan::new()
+        .path(&root)
+        .run()
+        .iter()
+        .map(|file| {
+            let producers: Vec<_> = file.producers().collect();
+            (file.path().strip_prefix(&root).unwrap().to_path_buf(), producers.join(", "))
+        })
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            ("app".into(), "Apple clang".into()),
+            (
+                "lib.dylib.dSYM/Contents/Resources/DWARF/lib.dylib".into(),
+                "Apple clang".into(),
+            ),
+        ]
+    );
+}
+
+#[test]
+fn universal_binaries() {
+    let path = support::write_fixture("universal/tree/app", &fixtures::universal_binary());
+
+    let all = dwprod::Options::new(&path).load_all().unwrap();
+    let arches: Vec<_> = all.iter().map(|info| info.arch()).collect();
+    assert_eq!(arches, vec![Some("x86_64"), Some("arm64")]);
+    assert_eq!(
+        all[0].producers().collect::<Vec<_>>().unwrap(),
+        vec!["clang for x86_64"]
+    );
+    assert_eq!(
+        all[1].producers().collect::<Vec<_>>().unwrap(),
+        vec!["clang for arm64"]
+    );
+
+    // `load` can't pick an architecture.
+    let err = dwprod::Options::new(&path).load().unwrap_err();
+    assert!(err.to_string().contains("Options::load_all"));
+
+    // A stripped universal binary's slices each find their own slice of a
+    // universal `.dSYM`, even when the slices are in a different order.
+    let stripped = fixtures::universal(
+        support::macho(object::Architecture::X86_64, &[]),
+        support::macho(object::Architecture::Aarch64, &[]),
+    );
+    let stripped = support::write_fixture("universal/stripped/app", &stripped);
+    support::write_fixture(
+        "universal/stripped/app.dSYM/Contents/Resources/DWARF/app",
+        &support::fat(&[
+            (
+                object::macho::CPU_TYPE_ARM64,
+                object::macho::CPU_SUBTYPE_ARM64_ALL,
+                fixtures::macho_with_producer(object::Architecture::Aarch64, "clang for arm64"),
+            ),
+            (
+                object::macho::CPU_TYPE_X86_64,
+                object::macho::CPU_SUBTYPE_X86_64_ALL,
+                fixtures::macho_with_producer(object::Architecture::X86_64, "clang for x86_64"),
+            ),
+        ]),
+    );
+    let all = dwprod::Options::new(&stripped).load_all().unwrap();
+    let producers: Vec<_> = all
+        .iter()
+        .map(|info| (info.arch().unwrap(), info.producers().collect::<Vec<_>>().unwrap()))
+        .collect();
+    assert_eq!(
+        producers,
+        vec![
+            ("x86_64", vec!["clang for x86_64".to_string()]),
+            ("arm64", vec!["clang for arm64".to_string()]),
+        ]
+    );
+
+    // Scans report each architecture, and skip Java class files, which share
+    // the universal binary magic number.
+    support::write_fixture(
+        "universal/tree/Main.class",
+        &[0xca, 0xfe, 0xba, 0xbe, 0x00, 0x00, 0x00, 0x34],
+    );
+    let files = dwprod::Scan::new().path(path.parent().unwrap()).run();
+    let scanned: Vec<_> = files
+        .iter()
+        .map(|file| (file.path(), file.arch(), file.producers().collect::<Vec<_>>()))
+        .collect();
+    assert_eq!(
+        scanned,
+        vec![
+            (path.as_path(), Some("x86_64"), vec!["clang for x86_64"]),
+            (path.as_path(), Some("arm64"), vec!["clang for arm64"]),
+        ]
+    );
+}
+
+/// The path of a fixture checked in under `tests/fixtures`, such as the MinGW
+/// ones built by `tests/fixtures/mingw/build.sh`.
+fn checked_in_fixture(name: &str) -> std::path::PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name)
+}
+
+#[test]
+fn pe_images_with_long_section_names() {
+    let path = checked_in_fixture("mingw/app.exe");
+    assert_eq!(producers_of(&path), vec!["clang version 14.0.6"]);
+}
+
+#[test]
+#[cfg(feature = "compression")]
+fn pe_images_with_compressed_sections() {
+    // Unlike the other MinGW fixtures, this one is synthesized, as the GNU ld
+    // that built them can't compress the debug sections of PE images.
+    let dwarf = fixtures::dwarf_with_producers(&["GNU C17 13.2.0 -g"]);
+    let compressed: Vec<_> = dwarf
+        .sections()
+        .iter()
+        .map(|&(name, ref data)| support::gnu_zlib(name, data))
+        .collect();
+    let sections: Vec<_> = compressed
+        .iter()
+        .map(|(name, data)| (&name[..], data.clone()))
+        .collect();
+
+    let path = support::write_fixture("mingw/compressed.exe", &support::pe(&sections));
+    assert_eq!(producers_of(&path), vec!["GNU C17 13.2.0 -g"]);
+}
+
+#[test]
+fn coff_objects_with_long_section_names() {
+    let path = checked_in_fixture("mingw/main.o");
+    assert_eq!(producers_of(&path), vec!["clang version 14.0.6"]);
+
+    // COFF object files have no magic number, but directory scans still find
+    // them, along with PE images.
+    let notes = support::write_fixture("mingw/tree/notes.txt", b"d\x86 is not a COFF file header");
+    let root = notes.parent().unwrap();
+    for name in &["main.o", "app.exe"] {
+        std::fs::copy(checked_in_fixture(&format!("mingw/{}", name)), root.join(name)).unwrap();
+    }
+    let files = dwprod::Scan::new().path(root).run();
+    let paths: Vec<_> = files.iter().map(|file| file.path()).collect();
+    assert_eq!(paths, vec![root.join("app.exe"), root.join("main.o")]);
+    assert!(files.iter().all(|file| file.errors().next().is_none()));
+}
+
+#[test]
+fn wasm_modules() {
+    let data = fixtures::wasm_module("clang LLVM (rustc version 1.75.0 (82e1608df 2023-12-21))");
+    let path = support::write_fixture("wasm/tree/app.wasm", &data);
+    assert_eq!(
+        producers_of(&path),
+        vec!["clang LLVM (rustc version 1.75.0 (82e1608df 2023-12-21))"]
+    );
+
+    let info = dwprod::Options::new(&path).load().unwrap();
+    let wasm = info.wasm_producers().expect("should have a producers section");
+    let tool = |name: &str, version: &str| dwprod::WasmTool {
+        name: name.into(),
+        version: version.into(),
+    };
+    assert_eq!(wasm.language(), [tool("Rust", "")]);
+    assert_eq!(
+        wasm.processed_by(),
+        [
+            tool("rustc", "1.75.0 (82e1608df 2023-12-21)"),
+            tool("wasm-bindgen", "0.2.89"),
+        ]
+    );
+    assert_eq!(wasm.sdk(), []);
+
+    // Release builds have no DWARF, but still have a `producers` section.
+    let release = support::wasm(&[("producers", fixtures::rust_wasm_producers())]);
+    let info = dwprod::Options::from_vec(release.clone()).load().unwrap();
+    assert!(info.compilation_units().next().unwrap().is_none());
+    assert_eq!(info.wasm_producers().unwrap().language(), [tool("Rust", "")]);
+
+    // Without either, there's nothing to report, which is only an error
+    // without the toolchain fallback.
+    let err = dwprod::Options::from_vec(support::wasm(&[])).load().unwrap_err();
+    assert_eq!(err.to_string(), "missing .debug_info section");
+    let info = dwprod::Options::from_vec(support::wasm(&[]))
+        .toolchain_fallback(true)
+        .load()
+        .unwrap();
+    assert!(info.compilation_units().next().unwrap().is_none());
+    assert!(info.wasm_producers().is_none());
+    assert_eq!(info.toolchain_records(), []);
+    assert!(dwprod::Options::from_bytes(b"\0asm\x01\0\0\0\0\x05").load().is_err());
+
+    // A truncated `producers` section is reported on its own, and doesn't keep
+    // the module's DWARF from being read.
+    let truncated = fixtures::wasm_module_with_truncated_producers("clang version 17.0.6");
+    let info = dwprod::Options::from_vec(truncated).load().unwrap();
+    assert_eq!(info.producers().collect::<Vec<_>>().unwrap(), vec!["clang version 17.0.6"]);
+    assert!(info.wasm_producers().is_none());
+    match info.wasm_producers_error() {
+        Some(dwprod::Error::Dwarf(gimli::Error::UnexpectedEof(_))) => {}
+        err => panic!("unexpected error: {:?}", err),
+    }
+
+    // Directory scans find WebAssembly modules by their magic number.
+    support::write_fixture("wasm/tree/release.wasm", &release);
+    let root = path.parent().unwrap();
+    let files = dwprod::Scan::new().path(root).run();
+    let paths: Vec<_> = files.iter().map(|file| file.path()).collect();
+    assert_eq!(paths, vec![root.join("app.wasm"), root.join("release.wasm")]);
+    assert!(files.iter().all(|file| file.wasm_producers().is_some()));
+}
+
+/// The member name and producers of each of the objects in the given file.
+fn member_producers(path: &Path) -> Vec<(String, Vec<String>)> {
+    dwprod::Options::new(path)
+        .load_all()
+        .expect("should load each member")
+        .iter()
+        .map(|info| {
+            let member = info.member().expect("should be a member").to_string();
+            (member, info.producers().collect().unwrap())
+        })
+        .collect()
+}
+
+#[test]
+fn static_libraries() {
+    let members: Vec<_> = fixtures::STATIC_LIBRARY_MEMBERS
+        .iter()
+        .map(|&(name, producer)| (name, support::Elf::new().producers(&[producer]).write()))
+        .collect();
+    let expected: Vec<_> = fixtures::STATIC_LIBRARY_MEMBERS
+        .iter()
+        .map(|&(name, producer)| (name.to_string(), vec![producer.to_string()]))
+        .collect();
+
+    for &(name, kind) in &[("libgnu.a", support::Ar::Gnu), ("libbsd.a", support::Ar::Bsd)] {
+        let data = fixtures::static_library(kind);
+        let path = support::write_fixture(&format!("archives/{}", name), &data);
+        assert_eq!(member_producers(&path), expected, "{}", name);
+
+        let err = dwprod::Options::new(&path).load().unwrap_err();
+        assert!(err.to_string().contains("Options::load_all"));
+    }
+
+    // The members of thin archives are files relative to the archive.
+    let thin_members: Vec<_> = members
+        .iter()
+        .map(|&(name, ref data)| {
+            support::write_fixture(&format!("archives/thin/objs/{}", name), data);
+            (format!("objs/{}", name), data.clone())
+        })
+        .collect();
+    let thin_members: Vec<_> = thin_members
+        .iter()
+        .map(|(name, data)| (&name[..], data.clone()))
+        .collect();
+    let thin = support::ar(support::Ar::Thin, &thin_members);
+    let path = support::write_fixture("archives/thin/libthin.a", &thin);
+    let all = dwprod::Options::new(&path).load_all().unwrap();
+    let members: Vec<_> = all.iter().map(|info| info.member().unwrap()).collect();
+    assert_eq!(members, ["objs/a.o", "objs/a_rather_long_member_name.o"]);
+    assert_eq!(all[0].path(), Some(&*path.with_file_name("objs/a.o")));
+    assert_eq!(
+        all[1].producers().collect::<Vec<_>>().unwrap(),
+        ["clang version 17.0.6"]
+    );
+
+    // Without the archive's path, there's nowhere to look for them.
+    assert!(dwprod::Options::from_vec(thin).load_all().is_err());
+}
+
+#[test]
+fn rlibs() {
+    let rmeta = support::Elf::new()
+        .section((".rmeta", object::SectionKind::ReadOnlyData, b"rust\0\0\0\x08".to_vec()))
+        .write();
+    let producer = "clang LLVM (rustc version 1.75.0 (82e1608df 2023-12-21))";
+    let rlib = support::ar(
+        support::Ar::Gnu,
+        &[
+            ("lib.rmeta", rmeta),
+            (
+                "foo-0123456789abcdef.foo.a1b2c3d4-cgu.0.rcgu.o",
+                support::Elf::new().producers(&[producer]).write(),
+            ),
+        ],
+    );
+    let path = support::write_fixture("archives/tree/libfoo-0123456789abcdef.rlib", &rlib);
+
+    // The metadata member has no DWARF, but isn't an object to inspect anyway.
+    let info = dwprod::Options::new(&path).load().unwrap();
+    assert_eq!(
+        info.member(),
+        Some("foo-0123456789abcdef.foo.a1b2c3d4-cgu.0.rcgu.o")
+    );
+    assert_eq!(info.producers().collect::<Vec<_>>().unwrap(), [producer]);
+
+    // Directory scans find archives, report each member separately, and carry
+    // on past the members that can't be read.
+    let truncated = support::Elf::new().write()[..32].to_vec();
+    let mixed = support::ar(
+        support::Ar::Gnu,
+        &[
+            ("truncated.o", truncated),
+            ("main.o", support::Elf::new().producers(&["GNU C17 12.2.0 -g"]).write()),
+        ],
+    );
+    support::write_fixture("archives/tree/libmixed.a", &mixed);
+    let root = path.parent().unwrap();
+    let files = dwprod::Scan::new().path(root).run();
+    let found: Vec<_> = files
+        .iter()
+        .map(|file| {
+            let producers: Vec<_> = file.producers().collect();
+            (file.path().file_name().unwrap(), file.member().unwrap(), producers)
+        })
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            (
+                "libfoo-0123456789abcdef.rlib".as_ref(),
+                "foo-0123456789abcdef.foo.a1b2c3d4-cgu.0.rcgu.o",
+                vec![producer],
+            ),
+            ("libmixed.a".as_ref(), "truncated.o", vec![]),
+            ("libmixed.a".as_ref(), "main.o", vec!["GNU C17 12.2.0 -g"]),
+        ]
+    );
+    let errors: Vec<_> = files[1].errors().collect();
+    assert!(matches!(errors[..], [dwprod::Error::Object(_)]), "{:?}", errors);
+}
+
+#[test]
+fn archive_members_without_dwarf() {
+    let producer = "GNU C17 12.2.0 -g";
+    let crt = support::Elf::new().section(support::comment(&["GCC: (GNU) 12.2.0"]));
+    let archive = support::ar(
+        support::Ar::Gnu,
+        &[
+            ("crt.o", crt.write()),
+            ("main.o", support::Elf::new().producers(&[producer]).write()),
+        ],
+    );
+    let path = support::write_fixture("archives/libnodwarf.a", &archive);
+
+    // Members without DWARF are skipped, rather than failing the archive.
+    let info = dwprod::Options::new(&path).load().unwrap();
+    assert_eq!(info.member(), Some("main.o"));
+    assert_eq!(info.producers().collect::<Vec<_>>().unwrap(), [producer]);
+    assert_eq!(member_producers(&path), [("main.o".to_string(), vec![producer.to_string()])]);
+
+    let files = dwprod::Scan::new().path(&path).run();
+    assert_eq!(files.len(), 1);
+    assert_eq!(files[0].member(), Some("main.o"));
+    assert_eq!(files[0].errors().count(), 0);
+
+    // With the toolchain fallback, they have toolchain records to report.
+    let all = dwprod::Options::new(&path).toolchain_fallback(true).load_all().unwrap();
+    let members: Vec<_> = all.iter().map(|info| info.member().unwrap()).collect();
+    assert_eq!(members, ["crt.o", "main.o"]);
+    let records: Vec<_> = all[0].toolchain_records().iter().map(|r| r.text()).collect();
+    assert_eq!(records, ["GCC: (GNU) 12.2.0"]);
+
+    // Unlike a standalone object, an archive without any DWARF at all is
+    // only an error for `Options::load`.
+    let bare = support::ar(support::Ar::Gnu, &[("crt.o", support::Elf::new().write())]);
+    assert!(dwprod::Options::from_vec(bare.clone()).load_all().unwrap().is_empty());
+    match dwprod::Options::from_vec(bare).load().unwrap_err() {
+        dwprod::Error::MissingSection(name) => assert_eq!(name, ".debug_info"),
+        err => panic!("unexpected error: {:?}", err),
+    }
+}
+
+#[test]
+fn toolchain_fallback_for_stripped_binaries() {
+    let path = support::write_fixture("stripped/app", &fixtures::stripped_binary());
+
+    // Without the fallback, there's nothing to go on.
+    let err = dwprod::Options::new(&path).load().unwrap_err();
+    assert_eq!(err.to_string(), "missing .debug_info section");
+
+    let info = dwprod::Options::new(&path)
+        .toolchain_fallback(true)
+        .load()
+        .unwrap();
+    assert!(info.compilation_units().next().unwrap().is_none());
+    let records: Vec<_> = info
+        .toolchain_records()
+        .iter()
+        .map(|record| record.to_string())
+        .collect();
+    assert_eq!(
+        records,
+        [
+            ".comment: GCC: (GNU) 12.2.0",
+            ".comment: rustc version 1.75.0 (82e1608df 2023-12-21)",
+            ".comment: Linker: LLD 17.0.6",
+            ".note.gnu.property: x86 feature: IBT, SHSTK",
+            ".note.gnu.property: x86 ISA needed: x86-64-baseline",
+        ]
+    );
+    let record = &info.toolchain_records()[0];
+    assert_eq!(record.source(), dwprod::RecordSource::Comment);
+    assert_eq!(record.text(), "GCC: (GNU) 12.2.0");
+
+    // Files with DWARF are unaffected.
+    let dwarf = fixtures::dwarf_with_producers(&["GNU C17 12.2.0 -g"]);
+    let data = support::Elf::new()
+        .section(support::comment(&["GCC: (GNU) 12.2.0"]))
+        .dwarf(&dwarf)
+        .write();
+    let info = dwprod::Options::from_vec(data)
+        .toolchain_fallback(true)
+        .load()
+        .unwrap();
+    assert!(info.toolchain_records().is_empty());
+    assert_eq!(
+        info.producers().collect::<Vec<_>>().unwrap(),
+        ["GNU C17 12.2.0 -g"]
+    );
+}
+
+#[test]
+fn type_units() {
+    use dwprod::UnitKind;
+
+    let (data, type_unit_offset) = fixtures::type_units_object();
+    let path = support::write_fixture("type-units/app", &data);
+
+    // Type units are left out by default.
+    let units = dwprod::Options::new(&path)
+        .compilation_units(|units| units.collect::<Vec<_>>())
+        .unwrap()
+        .unwrap();
+    assert!(units.iter().all(|unit| unit.kind() == UnitKind::Compile));
+    assert_eq!(
+        producers_of(&path),
+        ["clang version 17.0.6 -gdwarf-4", "clang version 17.0.6 -gdwarf-5"]
+    );
+
+    // DWARF 5 type units come in `.debug_info` order, and then the ones in
+    // `.debug_types`.
+    let units = dwprod::Options::new(&path)
+        .type_units(true)
+        .compilation_units(|units| units.collect::<Vec<_>>())
+        .unwrap()
+        .unwrap();
+    let kinds: Vec<_> = units.iter().map(|unit| unit.kind()).collect();
+    assert_eq!(
+        kinds,
+        [UnitKind::Compile, UnitKind::Type, UnitKind::Compile, UnitKind::Type]
+    );
+    let producers: Vec<_> = units
+        .iter()
+        .map(|unit| unit.producer().unwrap().raw())
+        .collect();
+    assert_eq!(
+        producers,
+        [
+            "clang version 17.0.6 -gdwarf-4",
+            "clang version 17.0.6 -gdwarf-5 -fdebug-types-section",
+            "clang version 17.0.6 -gdwarf-5",
+            "clang version 17.0.6 -gdwarf-4 -fdebug-types-section",
+        ]
+    );
+    assert_eq!(units[1].offset(), type_unit_offset);
+    assert_eq!(units[1].version(), 5);
+    assert_eq!(units[1].language_name(), Some("DW_LANG_C_plus_plus_14"));
+    // The `.debug_types` section's offsets start over.
+    assert_eq!(units[3].offset(), 0);
+    assert_eq!(units[3].version(), 4);
+
+    let files = dwprod::Scan::new().path(&path).type_units(true).run();
+    assert_eq!(files[0].producers().count(), 4);
+}
+
+#[test]
+fn lenient_mode() {
+    let (data, broken) = fixtures::damaged_object();
+    let path = support::write_fixture("damaged/app", &data);
+
+    // A corrupt unit header ends the iteration of `.debug_info`.
+    let info = dwprod::Options::new(&path).load().unwrap();
+    let mut producers = info.producers();
+    assert_eq!(producers.next().unwrap().unwrap(), "GNU C17 13.2.0 -g");
+    assert!(producers.next().is_err());
+    assert!(producers.next().unwrap().is_none());
+    assert!(producers.diagnostics().is_empty());
+
+    // When lenient, it is skipped over, and so are the units that can't be
+    // read, without any errors.
+    let info = dwprod::Options::new(&path).lenient(true).load().unwrap();
+    let mut producers = info.producers();
+    assert_eq!(
+        producers.by_ref().collect::<Vec<_>>().unwrap(),
+        ["GNU C17 13.2.0 -g", "clang version 17.0.6", "GNU C17 13.2.0 -O2"]
+    );
+    let diagnostics = producers.diagnostics();
+    let offsets: Vec<_> = diagnostics.iter().map(|d| d.offset()).collect();
+    assert_eq!(offsets, broken);
+    assert!(diagnostics.iter().all(|d| d.section() == ".debug_info"));
+    assert_eq!(
+        diagnostics[1].to_string(),
+        format!("unit at .debug_info+{:#x}: missing .debug_str section", broken[1])
+    );
+
+    let files = dwprod::Scan::new().path(&path).lenient(true).run();
+    assert_eq!(files[0].producers().count(), 3);
+    assert_eq!(files[0].errors().count(), 0);
+    let offsets: Vec<_> = files[0].diagnostics().iter().map(|d| d.offset()).collect();
+    assert_eq!(offsets, broken);
+}
+
+#[test]
+fn structured_errors() {
+    use dwprod::Error;
+    use support::{UnitSpec, Value};
+    use std::error::Error as _;
+
+    let err = dwprod::Options::from_bytes(b"just some text\n").load().unwrap_err();
+    assert!(matches!(err, Error::UnsupportedFormat(_)), "{:?}", err);
+
+    let mut module = support::wasm(&[]);
+    module[4] = 2;
+    match dwprod::Options::from_vec(module).load().unwrap_err() {
+        Error::UnsupportedFormat(found) => assert_eq!(found, "WebAssembly version 2"),
+        err => panic!("unexpected error: {:?}", err),
+    }
+
+    // Truncated universal binaries and archives say what was cut off.
+    let mut universal = fixtures::universal_binary();
+    let len = universal.len();
+    universal.truncate(len - 1);
+    match dwprod::Options::from_vec(universal).load_all().unwrap_err() {
+        Error::OutOfBounds { what, offset, size } => {
+            assert_eq!(what, "fat arch");
+            assert_eq!(offset + size, len as u64);
+        }
+        err => panic!("unexpected error: {:?}", err),
+    }
+    let mut archive = fixtures::static_library(support::Ar::Gnu);
+    let len = archive.len();
+    archive.truncate(len - 1);
+    match dwprod::Options::from_vec(archive).load_all().unwrap_err() {
+        Error::OutOfBounds { what, .. } => assert_eq!(what, "archive member"),
+        err => panic!("unexpected error: {:?}", err),
+    }
+    match dwprod::Options::from_vec(fixtures::universal_binary()).load().unwrap_err() {
+        Error::MultipleObjects(count) => assert_eq!(count, 2),
+        err => panic!("unexpected error: {:?}", err),
+    }
+
+    match dwprod::Options::from_vec(fixtures::stripped_binary()).load().unwrap_err() {
+        Error::MissingSection(name) => assert_eq!(name, ".debug_info"),
+        err => panic!("unexpected error: {:?}", err),
+    }
+
+    // Errors reading a unit say which unit it was.
+    let mut dwarf = support::DwarfBuilder::new(object::Endianness::Little);
+    dwarf.unit(&UnitSpec::version(4).attr(gimli::DW_AT_producer, Value::String("ok".into())));
+    dwarf.unit(&UnitSpec::version(4).attr(gimli::DW_AT_producer, Value::Data8(17)));
+    let data8_offset = dwarf.last_unit_offset();
+    dwarf.unit(&UnitSpec::new("refers to .debug_str"));
+    let strp_offset = dwarf.last_unit_offset();
+    let data = support::Elf::new().dwarf(&dwarf).without(".debug_str").write();
+    let path = support::write_fixture("structured-errors.o", &data);
+
+    let info = dwprod::Options::new(&path).load().unwrap();
+    let mut units = info.compilation_units();
+    assert!(units.next().unwrap().is_some());
+
+    let err = units.next().unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        format!(
+            "unit at .debug_info+{:#x}: unsupported form DW_FORM_data8 for DW_AT_producer",
+            data8_offset
+        )
+    );
+    match err {
+        Error::Unit {
+            path: ref unit_path,
+            section,
+            offset,
+            ref error,
+        } => {
+            assert_eq!(unit_path.as_deref(), Some(&*path));
+            assert_eq!(section, ".debug_info");
+            assert_eq!(offset, data8_offset);
+            match **error {
+                Error::UnsupportedForm {
+                    attribute: Some(gimli::DW_AT_producer),
+                    form: gimli::DW_FORM_data8,
+                } => {}
+                ref error => panic!("unexpected error: {:?}", error),
+            }
+        }
+        ref err => panic!("unexpected error: {:?}", err),
+    }
+
+    let err = units.next().unwrap_err();
+    match err {
+        Error::Unit { offset, ref error, .. } => {
+            assert_eq!(offset, strp_offset);
+            assert!(matches!(**error, Error::MissingSection(ref name) if name == ".debug_str"));
+        }
+        ref err => panic!("unexpected error: {:?}", err),
+    }
+    assert_eq!(err.source().unwrap().to_string(), "missing .debug_str section");
+    assert!(units.next().unwrap().is_none());
+}
+
+fn fixture_dir(name: &str) -> String {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+    dir.to_str().unwrap().to_string()
+}
+
+#[test]
+fn split_dwarf_dwo_files() {
+    use support::{UnitSpec, Value};
+
+    let comp_dir = fixture_dir("split-dwo");
+
+    let mut dwo = support::DwarfBuilder::new(object::Endianness::Little);
+    dwo.unit(
+        &UnitSpec::split(gimli::DW_UT_split_compile, 0x1122_3344_5566_7788).attr(
+            gimli::DW_AT_producer,
+            Value::Strx(gimli::DW_FORM_strx1, "GNU C17 12.2.0 -gsplit-dwarf".into()),
+        ),
+    );
+    let data = support::Elf::new().debug_sections(dwo.dwo_sections()).write();
+    support::write_fixture("split-dwo/main.dwo", &data);
+
+    // The GNU extension to DWARF 4.
+    let mut legacy = support::DwarfBuilder::new(object::Endianness::Little);
+    legacy.unit(
+        &UnitSpec::version(4)
+            .attr(gimli::DW_AT_GNU_dwo_id, Value::Data8(0xfeed))
+            .attr(gimli::DW_AT_producer, Value::String("GNU C99 4.8.5 -gsplit-dwarf".into())),
+    );
+    let data = support::Elf::new().debug_sections(legacy.dwo_sections()).write();
+    support::write_fixture("split-dwo/legacy.dwo", &data);
+
+    // Like LLVM's, the skeleton units have a `DW_AT_low_pc` indexing
+    // `.debug_addr`.
+    let mut dwarf = support::DwarfBuilder::new(object::Endianness::Little);
+    dwarf
+        .unit(
+            &UnitSpec::split(gimli::DW_UT_skeleton, 0x1122_3344_5566_7788)
+                .attr(gimli::DW_AT_comp_dir, Value::Strp(comp_dir.clone()))
+                .attr(gimli::DW_AT_dwo_name, Value::Strp("main.dwo".into()))
+                .attr(gimli::DW_AT_low_pc, Value::Addrx(gimli::DW_FORM_addrx, 0x1000)),
+        )
+        .unit(
+            &UnitSpec::split(gimli::DW_UT_skeleton, 0xdead)
+                .attr(gimli::DW_AT_comp_dir, Value::Strp(comp_dir.clone()))
+                .attr(gimli::DW_AT_dwo_name, Value::Strp("missing.dwo".into()))
+                .attr(gimli::DW_AT_low_pc, Value::Addrx(gimli::DW_FORM_addrx, 0x2000)),
+        )
+        .unit(
+            &UnitSpec::split(gimli::DW_UT_skeleton, 0xbad)
+                .attr(gimli::DW_AT_comp_dir, Value::Strp(comp_dir.clone()))
+                .attr(gimli::DW_AT_dwo_name, Value::Strp("main.dwo".into()))
+                .attr(gimli::DW_AT_low_pc, Value::Addrx(gimli::DW_FORM_addrx, 0x3000)),
+        )
+        .unit(
+            &UnitSpec::version(4)
+                .attr(gimli::DW_AT_GNU_dwo_name, Value::String("legacy.dwo".into()))
+                .attr(gimli::DW_AT_GNU_dwo_id, Value::Data8(0xfeed))
+                .attr(gimli::DW_AT_low_pc, Value::Addrx(gimli::DW_FORM_GNU_addr_index, 0x4000)),
+        )
+        .unit(&UnitSpec::new("rustc version 1.75.0"));
+    let path = support::write_fixture("split-dwo/app", &support::Elf::new().dwarf(&dwarf).write());
+
+    let dwo_path = Path::new(&comp_dir);
+    dwprod::Options::new(&path)
+        .producers(|producers| {
+            assert_eq!(
+                producers.next().unwrap(),
+                Some("GNU C17 12.2.0 -gsplit-dwarf".into())
+            );
+            let err = producers.next().unwrap_err();
+            assert!(err.to_string().contains("missing.dwo"));
+            match err {
+                dwprod::Error::Unit { error, .. } => match *error {
+                    dwprod::Error::SplitDwarfFile { path, error } => {
+                        assert_eq!(path, dwo_path.join("missing.dwo"));
+                        assert_eq!(error.kind(), std::io::ErrorKind::NotFound);
+                    }
+                    error => panic!("unexpected error: {:?}", error),
+                },
+                err => panic!("unexpected error: {:?}", err),
+            }
+            match producers.next().unwrap_err() {
+                dwprod::Error::Unit { error, .. } => match *error {
+                    dwprod::Error::MissingSplitUnit { path, dwo_id } => {
+                        assert_eq!(path, dwo_path.join("main.dwo"));
+                        assert_eq!(dwo_id, 0xbad);
+                    }
+                    error => panic!("unexpected error: {:?}", error),
+                },
+                err => panic!("unexpected error: {:?}", err),
+            }
+            assert_eq!(
+                producers.next().unwrap(),
+                Some("GNU C99 4.8.5 -gsplit-dwarf".into())
+            );
+            assert_eq!(producers.next().unwrap(), Some("rustc version 1.75.0".into()));
+            assert_eq!(producers.next().unwrap(), None);
+        })
+        .unwrap();
+}
+
+#[test]
+fn split_dwarf_dwp_package() {
+    use support::{UnitSpec, Value};
+
+    let mut dwp = support::DwarfBuilder::package(object::Endianness::Little);
+    let mut dwarf = support::DwarfBuilder::new(object::Endianness::Little);
+    let mut expected = vec![];
+    for &id in &[0x0123_4567_89ab_cdef, 0x42] {
+        let producer = format!("clang version 17.0.6 (unit {:#x})", id);
+        dwp.unit(
+            &UnitSpec::split(gimli::DW_UT_split_compile, id)
+                .attr(gimli::DW_AT_producer, Value::Strx(gimli::DW_FORM_strx1, producer.clone())),
+        );
+        // The `.dwo` files don't exist; only the package does.
+        dwarf.unit(
+            &UnitSpec::split(gimli::DW_UT_skeleton, id)
+                .attr(gimli::DW_AT_dwo_name, Value::Strp(format!("{:x}.dwo", id)))
+                .attr(gimli::DW_AT_low_pc, Value::Addrx(gimli::DW_FORM_addrx, id & 0xffff)),
+        );
+        expected.push(producer);
+    }
+
+    let data = support::Elf::new().debug_sections(dwp.dwp_sections()).write();
+    support::write_fixture("split-dwp/app.dwp", &data);
+    let path = support::write_fixture("split-dwp/app", &support::Elf::new().dwarf(&dwarf).write());
+    assert_eq!(producers_of(&path), expected);
+}
+
+#[test]
+fn dwz_supplementary_file() {
+    use support::{UnitSpec, Value};
+
+    let sup_build_id = [0x5u8; 20];
+    let mut sup = support::DwarfBuilder::new(object::Endianness::Little);
+    let shared = sup.string("GNU C17 11.3.1 -O2 -g");
+    sup.unit(&UnitSpec {
+        tag: gimli::DW_TAG_partial_unit,
+        ..UnitSpec::new("GNU C++17 11.3.1 -O2 -g")
+    });
+    let sup_partial = sup.last_root_offset();
+    let sup = support::Elf::new()
+        .section(support::build_id_note(&sup_build_id))
+        .dwarf(&sup)
+        .write();
+    support::write_fixture("dwz/.dwz/common.debug", &sup);
+
+    let import = |value| vec![(gimli::DW_AT_import, value)];
+    let mut dwarf = support::DwarfBuilder::new(object::Endianness::Little);
+    dwarf.unit(&UnitSpec {
+        tag: gimli::DW_TAG_partial_unit,
+        ..UnitSpec::new("GNU C11 11.3.1 -O2 -g")
+    });
+    let partial = dwarf.last_root_offset();
+    dwarf
+        .unit(&UnitSpec::version(4).attr(gimli::DW_AT_producer, Value::GnuStrpAlt(shared)))
+        .unit(&UnitSpec::version(4).child(
+            gimli::DW_TAG_imported_unit,
+            import(Value::RefAddr(partial)),
+        ))
+        .unit(&UnitSpec::version(4).child(
+            gimli::DW_TAG_imported_unit,
+            import(Value::GnuRefAlt(sup_partial)),
+        ));
+
+    let expected = vec![
+        "GNU C17 11.3.1 -O2 -g",
+        "GNU C11 11.3.1 -O2 -g",
+        "GNU C++17 11.3.1 -O2 -g",
+    ];
+
+    let exe = support::Elf::new()
+        .section(support::debugaltlink(".dwz/common.debug", &sup_build_id))
+        .dwarf(&dwarf)
+        .write();
+    let exe = support::write_fixture("dwz/app", &exe);
+    assert_eq!(producers_of(&exe), expected);
+
+    // Without the supplementary file, only the units that refer to it fail.
+    let exe = support::Elf::new()
+        .section(support::debugaltlink(".dwz/missing.debug", &sup_build_id))
+        .dwarf(&dwarf)
+        .write();
+    let exe = support::write_fixture("dwz/app-without-sup", &exe);
+    let missing_sup = |err: dwprod::Error| match err {
+        dwprod::Error::Unit { error, .. } => {
+            assert!(matches!(*error, dwprod::Error::MissingSupplementaryFile), "{:?}", error);
+        }
+        err => panic!("unexpected error: {:?}", err),
+    };
+    dwprod::Options::new(&exe)
+        .producers(|producers| {
+            missing_sup(producers.next().unwrap_err());
+            assert_eq!(producers.next().unwrap(), Some(expected[1].into()));
+            missing_sup(producers.next().unwrap_err());
+            assert_eq!(producers.next().unwrap(), None);
+        })
+        .unwrap();
+
+    // An import of an offset that isn't within any unit.
+    let mut dangling = support::DwarfBuilder::new(object::Endianness::Little);
+    dangling.unit(&UnitSpec::version(4).child(
+        gimli::DW_TAG_imported_unit,
+        import(Value::RefAddr(0xffff)),
+    ));
+    let data = support::Elf::new().dwarf(&dangling).write();
+    let path = support::write_fixture("dwz/dangling", &data);
+    dwprod::Options::new(&path)
+        .producers(|producers| match producers.next().unwrap_err() {
+            dwprod::Error::Unit { error, .. } => {
+                let dangling = matches!(*error, dwprod::Error::MissingImportedUnit(0xffff));
+                assert!(dangling, "{:?}", error);
+            }
+            err => panic!("unexpected error: {:?}", err),
+        })
+        .unwrap();
+}
+
+/// Lay out a directory tree with object files, non-object files, and symbolic
+/// links for scanning.
+fn scan_fixture(name: &str) -> std::path::PathBuf {
+    let root = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+    let _ = std::fs::remove_dir_all(&root);
+
+    let gcc = support::Elf::new().producers(&["GNU C17 12.2.0 -g"]).write();
+    let clang = support::Elf::new().producers(&["clang version 17.0.6"]).write();
+    support::write_fixture(&format!("{}/a.o", name), &gcc);
+    support::write_fixture(&format!("{}/README.txt", name), b"not an object file\n");
+    support::write_fixture(&format!("{}/empty", name), b"");
+    support::write_fixture(&format!("{}/sub/b.o", name), &clang);
+    support::write_fixture(&format!("{}/sub/stripped", name), &support::Elf::new().write());
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::symlink;
+        symlink(root.join("a.o"), root.join("link.o")).unwrap();
+        // Symbolic links to directories aren't followed, so this loop doesn't
+        // make the walk go on forever.
+        symlink(&root, root.join("sub/loop")).unwrap();
+    }
+
+    root
+}
+
+#[test]
+fn scan_directory_tree() {
+    let root = scan_fixture("scan");
+    let not_an_object = root.join("README.txt");
+
+    let files = dwprod::Scan::new().path(&root).path(&not_an_object).run();
+    let paths: Vec<_> = files.iter().map(|file| file.path().to_path_buf()).collect();
+
+    let mut expected = vec![root.join("a.o")];
+    if cfg!(unix) {
+        expected.push(root.join("link.o"));
+    }
+    expected.push(root.join("sub/b.o"));
+    expected.push(root.join("sub/stripped"));
+    // Files named explicitly are always inspected.
+    expected.push(not_an_object);
+    assert_eq!(paths, expected);
+
+    let producers: Vec<Vec<&str>> = files.iter().map(|file| file.producers().collect()).collect();
+    assert_eq!(producers[0], vec!["GNU C17 12.2.0 -g"]);
+    if cfg!(unix) {
+        assert_eq!(producers[1], vec!["GNU C17 12.2.0 -g"]);
+    }
+    assert_eq!(producers[producers.len() - 3], vec!["clang version 17.0.6"]);
+
+    let errors: Vec<Vec<String>> = files
+        .iter()
+        .map(|file| file.errors().map(|e| e.to_string()).collect())
+        .collect();
+    assert!(errors[..errors.len() - 2].iter().all(Vec::is_empty));
+    assert_eq!(errors[errors.len() - 2], vec!["missing .debug_info section"]);
+    assert_eq!(errors[errors.len() - 1].len(), 1);
+}
+
+#[test]
+fn scan_skips_split_dwarf_files() {
+    use support::{UnitSpec, Value};
+
+    let root = Path::new(env!("CARGO_TARGET_TMPDIR")).join("scan-split");
+    let _ = std::fs::remove_dir_all(&root);
+
+    // One binary with its split unit in a `.dwo` file, and another with its
+    // split unit in a `.dwp` package.
+    for &(name, id) in &[("dwo", 0x1), ("dwp", 0x2)] {
+        let producer = format!("clang version 17.0.6 ({})", name);
+        let split = UnitSpec::split(gimli::DW_UT_split_compile, id)
+            .attr(gimli::DW_AT_producer, Value::Strx(gimli::DW_FORM_strx1, producer));
+        let sections = if name == "dwo" {
+            let mut dwo = support::DwarfBuilder::new(object::Endianness::Little);
+            dwo.unit(&split);
+            dwo.dwo_sections()
+        } else {
+            let mut dwp = support::DwarfBuilder::package(object::Endianness::Little);
+            dwp.unit(&split);
+            dwp.dwp_sections()
+        };
+        let data = support::Elf::new().debug_sections(sections).write();
+        let split_path = format!("scan-split/{0}/app.{0}", name);
+        support::write_fixture(&split_path, &data);
+
+        let comp_dir = fixture_dir(&format!("scan-split/{}", name));
+        let mut dwarf = support::DwarfBuilder::new(object::Endianness::Little);
+        dwarf.unit(
+            &UnitSpec::split(gimli::DW_UT_skeleton, id)
+                .attr(gimli::DW_AT_comp_dir, Value::Strp(comp_dir))
+                .attr(gimli::DW_AT_dwo_name, Value::Strp("app.dwo".into())),
+        );
+        let data = support::Elf::new().dwarf(&dwarf).write();
+        support::write_fixture(&format!("scan-split/{}/app", name), &data);
+    }
+
+    let files = dwprod::Scan::new().path(&root).run();
+    let paths: Vec<_> = files.iter().map(|file| file.path().to_path_buf()).collect();
+    assert_eq!(paths, vec![root.join("dwo/app"), root.join("dwp/app")]);
+    let producers: Vec<Vec<&str>> = files.iter().map(|file| file.producers().collect()).collect();
+    assert_eq!(
+        producers,
+        vec![vec!["clang version 17.0.6 (dwo)"], vec!["clang version 17.0.6 (dwp)"]]
+    );
+    assert!(files.iter().all(|file| file.errors().next().is_none()));
+
+    // Named explicitly, they are still inspected.
+    let files = dwprod::Scan::new().path(root.join("dwo/app.dwo")).run();
+    let errors: Vec<_> = files[0].errors().map(|e| e.to_string()).collect();
+    assert_eq!(errors, vec!["missing .debug_info section"]);
+}
+
+#[test]
+#[cfg(feature = "exe")]
+fn read_from_stdin() {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let root = scan_fixture("scan-stdin");
+    let data = std::fs::read(root.join("a.o")).unwrap();
+
+    let mut child = Command::new(env!("DWPROD_EXE"))
+        .arg("-")
+        .arg(root.join("sub/b.o"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(&data).unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        str::from_utf8(&output.stdout).unwrap(),
+        format!(
+            "<stdin>: GNU C17 12.2.0 -g\n{}: clang version 17.0.6\n",
+            root.join("sub/b.o").display()
+        )
+    );
+
+    // Standard input can't be read twice.
+    let output = Command::new(env!("DWPROD_EXE"))
+        .args(["-", "-"])
+        .stdin(Stdio::null())
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+    assert_eq!(
+        str::from_utf8(&output.stderr).unwrap(),
+        "Error: standard input (`-`) can only be given once\n"
+    );
+}
+
+#[test]
+#[cfg(feature = "exe")]
+fn scan_from_the_command_line() {
+    let root = scan_fixture("scan-cli");
+    let b = root.join("sub/b.o");
+
+    // A single file isn't prefixed with its path.
+    let output = Command::new(env!("DWPROD_EXE")).arg(&b).output().unwrap();
+    assert!(output.status.success());
+    assert_eq!(str::from_utf8(&output.stdout).unwrap(), "clang version 17.0.6\n");
+
+    // But several files, or directories, are.
+    let output = Command::new(env!("DWPROD_EXE")).arg(root.join("sub")).output().unwrap();
+    assert!(!output.status.success());
+    assert_eq!(
+        str::from_utf8(&output.stdout).unwrap(),
+        format!("{}: clang version 17.0.6\n", b.display())
+    );
+    assert_eq!(
+        str::from_utf8(&output.stderr).unwrap(),
+        format!(
+            "Error: {}: missing .debug_info section\n",
+            root.join("sub/stripped").display()
+        )
+    );
+
+    let output = Command::new(env!("DWPROD_EXE"))
+        .arg(root.join("a.o"))
+        .arg(&b)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        str::from_utf8(&output.stdout).unwrap(),
+        format!(
+            "{}: GNU C17 12.2.0 -g\n{}: clang version 17.0.6\n",
+            root.join("a.o").display(),
+            b.display()
+        )
+    );
+}
+
+#[test]
+#[cfg(feature = "rayon")]
+fn parallel_scan_is_deterministic() {
+    use support::UnitSpec;
+
+    let mut paths = vec![];
+    for i in 0..8 {
+        let mut dwarf = support::DwarfBuilder::new(object::Endianness::Little);
+        for j in 0..500 {
+            // Scatter some units whose producer can't be read among the rest.
+            if j % 97 == 0 {
+                dwarf.unit(&UnitSpec::new("refers to .debug_str"));
+            } else {
+                dwarf.unit(&UnitSpec::version(4).attr(
+                    gimli::DW_AT_producer,
+                    support::Value::String(format!("GNU C17 12.2.0 -ffile-{}-{}", i, j)),
+                ));
+            }
+        }
+        let data = support::Elf::new().dwarf(&dwarf).without(".debug_str").write();
+        paths.push(support::write_fixture(&format!("parallel/{}.o", i), &data));
+    }
+
+    let run = |parallel| {
+        let mut scan = dwprod::Scan::new().parallel(parallel);
+        for path in &paths {
+            scan = scan.path(path);
+        }
+        scan.run()
+            .iter()
+            .map(|file| {
+                let results: Vec<_> = file
+                    .results()
+                    .iter()
+                    .map(|result| match *result {
+                        Ok(ref unit) => Ok(unit.clone()),
+                        Err(ref e) => Err(e.to_string()),
+                    })
+                    .collect();
+                (file.path().to_path_buf(), results)
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let sequential = run(false);
+    assert_eq!(sequential.len(), 8);
+    assert_eq!(sequential[0].1.len(), 500);
+    assert!(sequential[0].1[0].is_err());
+    assert_eq!(
+        sequential[3].1[1].as_ref().unwrap().producer().unwrap().raw(),
+        "GNU C17 12.2.0 -ffile-3-1"
+    );
+
+    for _ in 0..4 {
+        assert_eq!(run(true), sequential);
+    }
+}
+
+#[test]
+#[cfg(unix)]
+fn read_from_a_fifo() {
+    use std::io::Write;
+
+    // Pipes can't be memory mapped, so they're read into memory instead.
+    let path = Path::new(env!("CARGO_TARGET_TMPDIR")).join("fifo.o");
+    let _ = std::fs::remove_file(&path);
+    let status = Command::new("mkfifo").arg(&path).status().expect("should run mkfifo");
+    assert!(status.success());
+
+    let data = support::Elf::new().producers(&["GNU C17 12.2.0 -pipe"]).write();
+    let writer = {
+        let path = path.clone();
+        std::thread::spawn(move || {
+            let mut fifo = std::fs::OpenOptions::new().write(true).open(path).unwrap();
+            fifo.write_all(&data).unwrap();
+        })
+    };
+
+    assert_eq!(producers_of(&path), vec!["GNU C17 12.2.0 -pipe"]);
+    writer.join().unwrap();
+}